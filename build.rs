@@ -0,0 +1,196 @@
+//! Bake the stroke font in `src/render/font.txt` into a signed distance field atlas,
+//! so that labels stay sharp however large they are drawn
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+const FONT: &str = "src/render/font.txt";
+
+/// Glyphs per row of the atlas
+const COLUMNS: usize = 16;
+
+/// Pixels of the atlas per unit of the font
+const SCALE: usize = 4;
+
+/// Each cell holds a glyph 4 units wide and 8 tall, including descenders, with this
+/// many units to spare on every side for the field to fade out
+const MARGIN: usize = 1;
+const CELL_WIDTH: usize = 4 + 2 * MARGIN;
+const CELL_HEIGHT: usize = 8 + 2 * MARGIN;
+
+/// Half the thickness of the strokes, in units
+const STROKE_RADIUS: f32 = 0.45;
+
+/// Distance from the edge of a stroke, in units, at which the field reaches 0 or 255
+const SPREAD: f32 = 1.0;
+
+type Point = [f32; 2];
+
+/// Points along an ellipse, from one angle to another in degrees
+fn arc(center: Point, radii: Point, from: f32, to: f32) -> Vec<Point> {
+    #![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #![allow(clippy::cast_precision_loss)]
+
+    let segments = ((to - from).abs() / 10.0).ceil().max(1.0) as usize;
+
+    (0..=segments)
+        .map(|i| {
+            let angle = (from + (to - from) * i as f32 / segments as f32).to_radians();
+            [
+                center[0] + radii[0] * angle.cos(),
+                center[1] + radii[1] * angle.sin(),
+            ]
+        })
+        .collect()
+}
+
+fn point(text: &str) -> Point {
+    let mut values = text.split(',').map(|value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("Bad coordinate '{}' in {}", text, FONT))
+    });
+
+    match (values.next(), values.next(), values.next()) {
+        (Some(x), Some(y), None) => [x, y],
+        _ => panic!("Bad point '{}' in {}", text, FONT),
+    }
+}
+
+fn stroke(text: &str) -> Vec<Point> {
+    let fields: Vec<&str> = text.split_whitespace().collect();
+
+    match fields.as_slice() {
+        ["arc", center, radii, from, to] => {
+            let angle = |text: &str| {
+                text.parse()
+                    .unwrap_or_else(|_| panic!("Bad angle '{}' in {}", text, FONT))
+            };
+            arc(point(center), point(radii), angle(from), angle(to))
+        }
+        points => points.iter().map(|text| point(text)).collect(),
+    }
+}
+
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f32 {
+    let ab = [b[0] - a[0], b[1] - a[1]];
+    let ap = [p[0] - a[0], p[1] - a[1]];
+    let length2 = ab[0] * ab[0] + ab[1] * ab[1];
+
+    let t = if length2 > 0.0 {
+        ((ap[0] * ab[0] + ap[1] * ab[1]) / length2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let dx = ap[0] - t * ab[0];
+    let dy = ap[1] - t * ab[1];
+    (dx * dx + dy * dy).sqrt()
+}
+
+fn main() {
+    #![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #![allow(clippy::cast_precision_loss)]
+
+    println!("cargo:rerun-if-changed={}", FONT);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let font = fs::read_to_string(FONT).expect("Could not read the font");
+
+    let mut characters = String::new();
+    let mut glyphs = Vec::<Vec<Vec<Point>>>::new();
+
+    for line in font.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+
+        let (character, strokes) = if let Some(strokes) = line.strip_prefix("space") {
+            (' ', strokes)
+        } else {
+            let mut chars = line.chars();
+            let character = chars.next().unwrap();
+            (character, chars.as_str())
+        };
+
+        characters.push(character);
+        glyphs.push(
+            strokes
+                .split(';')
+                .filter(|text| !text.trim().is_empty())
+                .map(stroke)
+                .collect(),
+        );
+    }
+
+    let rows = glyphs.len().div_ceil(COLUMNS);
+    let width = COLUMNS * CELL_WIDTH * SCALE;
+    let height = rows * CELL_HEIGHT * SCALE;
+    let mut atlas = vec![0_u8; width * height];
+
+    for (index, strokes) in glyphs.iter().enumerate() {
+        let left = (index % COLUMNS) * CELL_WIDTH * SCALE;
+        let top = (index / COLUMNS) * CELL_HEIGHT * SCALE;
+
+        for y in 0..CELL_HEIGHT * SCALE {
+            for x in 0..CELL_WIDTH * SCALE {
+                // Rows of the atlas run from the top of each glyph down
+                let p = [
+                    (x as f32 + 0.5) / SCALE as f32 - MARGIN as f32,
+                    (CELL_HEIGHT - MARGIN) as f32 - (y as f32 + 0.5) / SCALE as f32,
+                ];
+
+                let distance = strokes
+                    .iter()
+                    .flat_map(|stroke| {
+                        let segments = stroke.windows(2).map(|pair| (pair[0], pair[1]));
+                        let dot = stroke.first().filter(|_| stroke.len() == 1);
+                        segments.chain(dot.map(|&dot| (dot, dot)))
+                    })
+                    .map(|(a, b)| distance_to_segment(p, a, b))
+                    .fold(f32::INFINITY, f32::min);
+
+                let value = 0.5 - (distance - STROKE_RADIUS) / (2.0 * SPREAD);
+                atlas[(top + y) * width + left + x] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("font.bin"), &atlas).unwrap();
+
+    let mut constants = String::new();
+    writeln!(constants, "// Generated by build.rs from {}", FONT).unwrap();
+    writeln!(constants, "pub const CHARACTERS: &str = {:?};", characters).unwrap();
+    writeln!(constants, "pub const ATLAS_COLUMNS: usize = {};", COLUMNS).unwrap();
+    writeln!(constants, "pub const ATLAS_ROWS: usize = {};", rows).unwrap();
+    writeln!(constants, "pub const ATLAS_WIDTH: i32 = {};", width).unwrap();
+    writeln!(constants, "pub const ATLAS_HEIGHT: i32 = {};", height).unwrap();
+    writeln!(
+        constants,
+        "pub const CELL_WIDTH: f32 = {:.1};",
+        CELL_WIDTH as f32
+    )
+    .unwrap();
+    writeln!(
+        constants,
+        "pub const CELL_HEIGHT: f32 = {:.1};",
+        CELL_HEIGHT as f32
+    )
+    .unwrap();
+    writeln!(
+        constants,
+        "pub const CELL_MARGIN: f32 = {:.1};",
+        MARGIN as f32
+    )
+    .unwrap();
+    writeln!(
+        constants,
+        "pub static ATLAS: &[u8] = include_bytes!(concat!(env!(\"OUT_DIR\"), \"/font.bin\"));"
+    )
+    .unwrap();
+    fs::write(Path::new(&out_dir).join("font.rs"), constants).unwrap();
+}
@@ -42,6 +42,17 @@ fn look_at_rh(eye: &Vec3, target: &Vec3, up: &Vec3) -> Mat4 {
     ]
 }
 
+fn perspective(width: u32, height: u32) -> Mat4 {
+    #![allow(clippy::cast_precision_loss)]
+    let aspect_ratio = (width as f32) / (height as f32);
+    Mat4::create_perspective(
+        60.0 * std::f32::consts::PI / 180.0,
+        aspect_ratio,
+        0.1,
+        1000.0,
+    )
+}
+
 pub struct Camera {
     projection: Mat4,
     target_position: Vec3,
@@ -53,15 +64,8 @@ pub struct Camera {
 
 impl Camera {
     pub fn new(width: u32, height: u32) -> Self {
-        #![allow(clippy::cast_precision_loss)]
-        let aspect_ratio = (width as f32) / (height as f32);
         Self {
-            projection: Mat4::create_perspective(
-                60.0 * std::f32::consts::PI / 180.0,
-                aspect_ratio,
-                0.1,
-                1000.0,
-            ),
+            projection: perspective(width, height),
             orbit_radius: 9.0,
             target_position: [0.0, 0.0, 0.0],
             left_right_radians: 0.0,
@@ -70,6 +74,11 @@ impl Camera {
         }
     }
 
+    /// Fit the projection to an image of another size, keeping the field of view
+    pub fn set_viewport_size(&mut self, width: u32, height: u32) {
+        self.projection = perspective(width, height);
+    }
+
     pub fn set_target_position(&mut self, new_target: &Vec3) {
         self.target_position = *new_target;
     }
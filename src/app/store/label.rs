@@ -0,0 +1,91 @@
+use std::str::FromStr;
+
+use super::AtomId;
+use crate::molecule::Molecule;
+
+/// Where a label is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    /// Following an atom as it moves
+    Atom(AtomId),
+    Point([f32; 3]),
+}
+
+/// Text drawn facing the camera at an anchor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub anchor: Anchor,
+    pub text: String,
+    pub color: [f32; 4],
+}
+
+impl Label {
+    /// A white label following an atom
+    pub fn atom(id: AtomId, text: impl Into<String>) -> Self {
+        Self {
+            anchor: Anchor::Atom(id),
+            text: text.into(),
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    /// A white label fixed in space
+    pub fn point(position: [f32; 3], text: impl Into<String>) -> Self {
+        Self {
+            anchor: Anchor::Point(position),
+            text: text.into(),
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    /// Where the label is centred, in the current frames of the molecules
+    pub fn position(&self, molecules: &[Molecule]) -> [f32; 3] {
+        match self.anchor {
+            Anchor::Atom(id) => molecules[id.molecule].atoms[id.atom].position,
+            Anchor::Point(point) => point,
+        }
+    }
+}
+
+/// What to write on the label of an atom
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelContent {
+    /// The atom name, or the element symbol for atoms without one
+    Name,
+    Element,
+    /// Position in `Molecule::atoms`, starting at 0
+    Index,
+    /// Serial number given by the file
+    Serial,
+    /// Residue name and number, such as `ALA 12`
+    Residue,
+}
+
+impl LabelContent {
+    pub fn text(self, molecule: &Molecule, atom: usize) -> String {
+        let atom_data = &molecule.atoms[atom];
+
+        match self {
+            Self::Name if !atom_data.name.is_empty() => atom_data.name.clone(),
+            Self::Name | Self::Element => atom_data.element.symbol().to_owned(),
+            Self::Index => atom.to_string(),
+            Self::Serial => atom_data.serial.to_string(),
+            Self::Residue => format!("{} {}", atom_data.residue_name, atom_data.residue_number),
+        }
+    }
+}
+
+impl FromStr for LabelContent {
+    type Err = String;
+
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        match content.to_ascii_lowercase().as_str() {
+            "name" => Ok(Self::Name),
+            "element" => Ok(Self::Element),
+            "index" => Ok(Self::Index),
+            "serial" => Ok(Self::Serial),
+            "residue" => Ok(Self::Residue),
+            _ => Err(format!("Unknown label content {}", content)),
+        }
+    }
+}
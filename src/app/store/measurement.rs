@@ -0,0 +1,122 @@
+use webgl_matrix::{Vec3, Vector};
+
+use crate::molecule::Molecule;
+
+fn cross(u: &Vec3, v: &Vec3) -> Vec3 {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+/// An atom of one of the molecules in `State::molecules`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AtomId {
+    pub molecule: usize,
+    pub atom: usize,
+}
+
+/// A distance, angle or dihedral between atoms, which may belong to different molecules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measurement {
+    Distance([AtomId; 2]),
+    /// The angle at the middle atom
+    Angle([AtomId; 3]),
+    /// The torsion about the bond between the middle two atoms
+    Dihedral([AtomId; 4]),
+}
+
+impl Measurement {
+    /// The measurement between two, three or four different atoms
+    ///
+    /// # Errors
+    ///
+    /// Fails for any other number of atoms, or when an atom is repeated, which would
+    /// leave the angles undefined
+    pub fn new(atoms: &[AtomId]) -> Result<Self, String> {
+        for (position, id) in atoms.iter().enumerate() {
+            if atoms[..position].contains(id) {
+                return Err(format!(
+                    "Atom {} of molecule {} is repeated",
+                    id.atom, id.molecule
+                ));
+            }
+        }
+
+        match *atoms {
+            [a, b] => Ok(Self::Distance([a, b])),
+            [a, b, c] => Ok(Self::Angle([a, b, c])),
+            [a, b, c, d] => Ok(Self::Dihedral([a, b, c, d])),
+            _ => Err("Measurements need two, three or four atoms".to_owned()),
+        }
+    }
+
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Distance(_) => "distance",
+            Self::Angle(_) => "angle",
+            Self::Dihedral(_) => "dihedral",
+        }
+    }
+
+    pub fn atoms(&self) -> &[AtomId] {
+        match self {
+            Self::Distance(atoms) => atoms,
+            Self::Angle(atoms) => atoms,
+            Self::Dihedral(atoms) => atoms,
+        }
+    }
+
+    /// The positions of the atoms in their current frames
+    pub fn positions(&self, molecules: &[Molecule]) -> Vec<Vec3> {
+        self.atoms()
+            .iter()
+            .map(|id| molecules[id.molecule].atoms[id.atom].position)
+            .collect()
+    }
+
+    /// The distance in Angstroms, or the angle in degrees. Dihedrals run from -180 to
+    /// 180 degrees, positive when clockwise looking down the middle bond.
+    pub fn value(&self, molecules: &[Molecule]) -> f32 {
+        match self.positions(molecules).as_slice() {
+            [a, b] => b.sub(a).mag(),
+            [a, b, c] => {
+                let (u, v) = (a.sub(b), c.sub(b));
+                let cosine = u.dot(&v) / (u.mag() * v.mag());
+                cosine.clamp(-1.0, 1.0).acos().to_degrees()
+            }
+            [a, b, c, d] => {
+                let (b1, b2, b3) = (b.sub(a), c.sub(b), d.sub(c));
+                let (n1, n2) = (cross(&b1, &b2), cross(&b2, &b3));
+                (b2.mag() * b1.dot(&n2)).atan2(n1.dot(&n2)).to_degrees()
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// The value as it is shown next to the measurement
+    pub fn label(&self, molecules: &[Molecule]) -> String {
+        match self {
+            Self::Distance(_) => format!("{:.2}", self.value(molecules)),
+            _ => format!("{:.1}°", self.value(molecules)),
+        }
+    }
+
+    /// Where the label is drawn: the middle of a distance or of the central bond of a
+    /// dihedral, and inside an angle near its vertex
+    pub fn label_position(&self, molecules: &[Molecule]) -> Vec3 {
+        match self.positions(molecules).as_slice() {
+            [a, b] | [_, a, b, _] => a.add(b).scale(0.5),
+            [a, b, c] => {
+                let towards = |end: &Vec3| {
+                    let direction = end.sub(b);
+                    direction.scale(1.0 / direction.mag().max(1e-6))
+                };
+                let bisector = towards(a).add(&towards(c));
+                b.add(&bisector.scale(0.5 / bisector.mag().max(1e-6)))
+            }
+            _ => [0.0, 0.0, 0.0],
+        }
+    }
+}
@@ -6,8 +6,30 @@ use self::mouse::*;
 mod camera;
 use self::camera::*;
 
+mod label;
+pub use self::label::*;
+
+mod measurement;
+pub use self::measurement::*;
+
+mod morph;
+use self::morph::*;
+
+mod pick;
+pub use self::pick::*;
+
+mod selection;
+pub use self::selection::*;
+
 use crate::molecule::*;
 
+/// How long each frame of a trajectory is shown, in milliseconds
+const FRAME_DURATION: f32 = 100.0;
+
+/// How far the mouse may move while pressed, in pixels, for it to still be a click
+const CLICK_TOLERANCE: i32 = 3;
+
+/// Positions of the mouse are in pixels from the top left corner of the canvas
 pub enum Msg {
     AdvanceClock(f32),
     MouseDown(i32, i32),
@@ -17,21 +39,49 @@ pub enum Msg {
     Key(String, bool),
 }
 
+/// The hydrogen bonds donated by one molecule to another, or to itself
+pub struct HydrogenBonds {
+    /// Indices into `State::molecules`
+    pub donors: usize,
+    pub acceptors: usize,
+    pub bonds: Vec<HydrogenBond>,
+}
+
 pub struct State {
+    /// Time elapsed since the application started, in milliseconds
     clock: f32,
     camera: Camera,
     mouse: Mouse,
     molecules: Vec<Molecule>,
+    /// Found again whenever a molecule is added or moves to another frame
+    hydrogen_bonds: Vec<HydrogenBonds>,
+    /// Indices of the donating and accepting molecules searched for hydrogen bonds
+    /// between them, on top of those within each molecule
+    hydrogen_bond_pairs: Vec<(usize, usize)>,
+    measurements: Vec<Measurement>,
+    /// Attached by JavaScript or other features, while measurements are labelled with
+    /// their values by the renderer
+    labels: Vec<Label>,
+    morphs: Vec<Morph>,
+    selection: Selection,
+    /// Clicks, and the latest place the mouse hovered, not yet picked by the renderer
+    picks: Vec<PickRequest>,
 }
 
 impl State {
     fn new(width: u32, height: u32) -> Self {
         Self {
-            /// Time elapsed since the application started, in milliseconds
             clock: 0.,
             camera: Camera::new(width, height),
             mouse: Mouse::default(),
             molecules: Vec::<Molecule>::new(),
+            hydrogen_bonds: Vec::new(),
+            hydrogen_bond_pairs: Vec::new(),
+            measurements: Vec::new(),
+            labels: Vec::new(),
+            morphs: Vec::new(),
+            selection: Selection::default(),
+            picks: Vec::new(),
         }
     }
 
@@ -43,6 +93,100 @@ impl State {
         &self.molecules
     }
 
+    pub fn hydrogen_bonds(&self) -> &[HydrogenBonds] {
+        &self.hydrogen_bonds
+    }
+
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    pub const fn selection(&self) -> &Selection {
+        &self.selection
+    }
+
+    fn check_atom(&self, id: AtomId) -> Result<(), String> {
+        let exists = self
+            .molecules
+            .get(id.molecule)
+            .is_some_and(|molecule| id.atom < molecule.atoms.len());
+
+        if exists {
+            Ok(())
+        } else {
+            Err(format!("No atom {} in molecule {}", id.atom, id.molecule))
+        }
+    }
+
+    /// Find the hydrogen bonds within each molecule, and between the chosen pairs of
+    /// molecules. Searching every pair would join the models of NMR ensembles or
+    /// multi-record files, which sit on top of each other.
+    fn update_hydrogen_bonds(&mut self) {
+        self.hydrogen_bonds.clear();
+        self.update_hydrogen_bonds_of(&(0..self.molecules.len()).collect::<Vec<_>>());
+    }
+
+    /// Find the hydrogen bonds again for the molecules which moved, keeping those
+    /// between molecules which stayed in place
+    fn update_hydrogen_bonds_of(&mut self, moved: &[usize]) {
+        self.hydrogen_bonds
+            .retain(|group| !moved.contains(&group.donors) && !moved.contains(&group.acceptors));
+
+        let pairs = moved.iter().map(|&index| (index, index)).chain(
+            self.hydrogen_bond_pairs
+                .iter()
+                .copied()
+                .filter(|(donors, acceptors)| moved.contains(donors) || moved.contains(acceptors)),
+        );
+
+        for (donors, acceptors) in pairs {
+            let bonds = if donors == acceptors {
+                self.molecules[donors].hydrogen_bonds()
+            } else {
+                self.molecules[donors].hydrogen_bonds_to(&self.molecules[acceptors])
+            };
+
+            if !bonds.is_empty() {
+                self.hydrogen_bonds.push(HydrogenBonds {
+                    donors,
+                    acceptors,
+                    bonds,
+                });
+            }
+        }
+    }
+
+    /// Search for the hydrogen bonds donated by the first molecule of each pair to the
+    /// second, replacing the pairs chosen before. Bonds within a molecule are always
+    /// found.
+    ///
+    /// # Errors
+    ///
+    /// Fails when one of the molecules does not exist
+    pub fn set_hydrogen_bond_pairs(&mut self, pairs: &[(usize, usize)]) -> Result<(), String> {
+        if let Some(index) = pairs
+            .iter()
+            .flat_map(|&(donors, acceptors)| [donors, acceptors])
+            .find(|&index| index >= self.molecules.len())
+        {
+            return Err(format!("No molecule {}", index));
+        }
+
+        self.hydrogen_bond_pairs.clear();
+        for &(donors, acceptors) in pairs {
+            if donors != acceptors && !self.hydrogen_bond_pairs.contains(&(donors, acceptors)) {
+                self.hydrogen_bond_pairs.push((donors, acceptors));
+            }
+        }
+
+        self.update_hydrogen_bonds();
+        Ok(())
+    }
+
     // The current time in milliseconds
     //pub fn clock(&self) -> f32 {
     //    self.clock
@@ -51,6 +195,250 @@ impl State {
     pub fn add_molecule(&mut self, molecule: Molecule) {
         self.camera.set_target_position(&molecule.center());
         self.molecules.push(molecule);
+        self.update_hydrogen_bonds();
+    }
+
+    /// Keep a measurement, which is drawn and updated as its atoms move
+    ///
+    /// # Errors
+    ///
+    /// Fails when one of the atoms does not exist
+    pub fn add_measurement(&mut self, measurement: Measurement) -> Result<(), String> {
+        for id in measurement.atoms() {
+            self.check_atom(*id)?;
+        }
+
+        self.measurements.push(measurement);
+        Ok(())
+    }
+
+    pub fn clear_measurements(&mut self) {
+        self.measurements.clear();
+    }
+
+    /// Keep a label, which follows its atom, if it has one, as it moves
+    ///
+    /// # Errors
+    ///
+    /// Fails when the label is attached to an atom which does not exist
+    pub fn add_label(&mut self, label: Label) -> Result<(), String> {
+        if let Anchor::Atom(id) = label.anchor {
+            self.check_atom(id)?;
+        }
+
+        self.labels.push(label);
+        Ok(())
+    }
+
+    /// Label every selected atom, returning how many there are
+    pub fn label_selection(&mut self, content: LabelContent) -> usize {
+        let labels: Vec<Label> = self
+            .selection
+            .iter()
+            .map(|id| Label::atom(id, content.text(&self.molecules[id.molecule], id.atom)))
+            .collect();
+
+        let count = labels.len();
+        self.labels.extend(labels);
+        count
+    }
+
+    pub fn clear_labels(&mut self) {
+        self.labels.clear();
+    }
+
+    /// Add atoms to the selection, remove them from it or toggle them
+    ///
+    /// # Errors
+    ///
+    /// Fails, leaving the selection as it was, when one of the atoms does not exist
+    pub fn change_selection(
+        &mut self,
+        change: SelectionChange,
+        atoms: &[AtomId],
+    ) -> Result<(), String> {
+        for id in atoms {
+            self.check_atom(*id)?;
+        }
+
+        self.selection.change(change, atoms);
+        Ok(())
+    }
+
+    /// Select exactly the atoms matching a query, returning how many there are
+    pub fn select(&mut self, query: &Query) -> usize {
+        let atoms: Vec<AtomId> = self
+            .molecules
+            .iter()
+            .enumerate()
+            .flat_map(|(molecule, atoms)| {
+                atoms
+                    .select(query)
+                    .into_iter()
+                    .map(move |atom| AtomId { molecule, atom })
+            })
+            .collect();
+
+        self.selection.clear();
+        self.selection.change(SelectionChange::Add, &atoms);
+        atoms.len()
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    pub fn invert_selection(&mut self) {
+        self.selection.invert(&self.molecules);
+    }
+
+    /// Move one molecule onto another, returning the RMSD of the paired atoms once
+    /// superposed. See `Molecule::superposition` for the `mapping`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when either molecule does not exist, or the atoms cannot be paired
+    pub fn superpose(
+        &mut self,
+        moving: usize,
+        reference: usize,
+        mapping: Option<&[(usize, usize)]>,
+    ) -> Result<f32, String> {
+        let missing = |index: usize| format!("No molecule {}", index);
+        let superposition = self
+            .molecules
+            .get(moving)
+            .ok_or_else(|| missing(moving))?
+            .superposition(
+                self.molecules
+                    .get(reference)
+                    .ok_or_else(|| missing(reference))?,
+                mapping,
+            )?;
+
+        self.molecules[moving].transform(&superposition);
+        self.update_hydrogen_bonds();
+
+        Ok(superposition.rmsd)
+    }
+
+    /// Change how a molecule is drawn
+    ///
+    /// # Errors
+    ///
+    /// Fails when the molecule does not exist
+    pub fn set_representation(
+        &mut self,
+        index: usize,
+        representation: Representation,
+    ) -> Result<(), String> {
+        let molecule = self
+            .molecules
+            .get_mut(index)
+            .ok_or_else(|| format!("No molecule {}", index))?;
+
+        molecule.representation = representation;
+        Ok(())
+    }
+
+    /// Draw dashed partial bonds between the atoms of a molecule just beyond bonding
+    /// distance, such as the forming and breaking bonds of a transition state
+    ///
+    /// # Errors
+    ///
+    /// Fails when the molecule does not exist
+    pub fn show_partial_bonds(&mut self, index: usize, show: bool) -> Result<(), String> {
+        let molecule = self
+            .molecules
+            .get_mut(index)
+            .ok_or_else(|| format!("No molecule {}", index))?;
+
+        molecule.show_partial_bonds(show);
+        Ok(())
+    }
+
+    /// Animate the first of `keyframes` through the geometries of the others and back,
+    /// taking `duration` milliseconds between consecutive keyframes. The other keyframes
+    /// must have the same elements in the same order. Their geometries are copied, so
+    /// they stay in the scene untouched. A trajectory of the animated molecule is paused
+    /// until `stop_morph`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when there are fewer than two keyframes, one does not exist or is repeated,
+    /// or their atoms differ
+    pub fn morph(&mut self, keyframes: &[usize], duration: f32) -> Result<(), String> {
+        let first = match keyframes {
+            [first, _, ..] => *first,
+            _ => return Err("A morph needs at least two keyframes".to_owned()),
+        };
+        if duration <= 0.0 {
+            return Err("The duration of a morph must be positive".to_owned());
+        }
+
+        let mut geometries = Vec::<Vec<[f32; 3]>>::with_capacity(keyframes.len());
+        for (position, &index) in keyframes.iter().enumerate() {
+            if keyframes[..position].contains(&index) {
+                return Err(format!("Molecule {} is repeated", index));
+            }
+
+            let molecule = self
+                .molecules
+                .get(index)
+                .ok_or_else(|| format!("No molecule {}", index))?;
+
+            let atoms = &self.molecules[first].atoms;
+            let same = molecule.atoms.len() == atoms.len()
+                && molecule
+                    .atoms
+                    .iter()
+                    .zip(atoms)
+                    .all(|(a, b)| a.element == b.element);
+            if !same {
+                return Err(format!(
+                    "Molecule {} does not have the atoms of molecule {}",
+                    index, first
+                ));
+            }
+
+            geometries.push(molecule.atoms.iter().map(|atom| atom.position).collect());
+        }
+
+        self.morphs.retain(|morph| morph.molecule != first);
+        self.morphs
+            .push(Morph::new(first, geometries, duration, self.clock));
+
+        self.update_hydrogen_bonds();
+        Ok(())
+    }
+
+    /// Stop animating a molecule, leaving it in the geometry of its first keyframe.
+    /// A trajectory paused by the animation plays again.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the molecule is not being animated
+    pub fn stop_morph(&mut self, index: usize) -> Result<(), String> {
+        let position = self
+            .morphs
+            .iter()
+            .position(|morph| morph.molecule == index)
+            .ok_or_else(|| format!("Molecule {} is not being animated", index))?;
+
+        let morph = self.morphs.remove(position);
+        self.molecules[index].set_positions(morph.first_keyframe());
+        self.update_hydrogen_bonds_of(&[index]);
+        Ok(())
+    }
+
+    /// Hand the clicks and hovers waiting to be picked over to the renderer
+    pub fn take_picks(&mut self) -> Vec<PickRequest> {
+        std::mem::take(&mut self.picks)
+    }
+
+    /// Fit the camera to an image of another size than the canvas
+    pub fn set_viewport_size(&mut self, width: u32, height: u32) {
+        self.camera.set_viewport_size(width, height);
     }
 
     pub fn msg(&mut self, msg: &Msg) {
@@ -58,16 +446,64 @@ impl State {
         match msg {
             Msg::AdvanceClock(dt) => {
                 self.clock += dt;
+
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let frame = (self.clock / FRAME_DURATION) as usize;
+
+                let mut moved = Vec::new();
+                for (index, molecule) in self.molecules.iter_mut().enumerate() {
+                    // Stepping through the frames of a trajectory would fight an
+                    // animation, so it waits for the animation to stop
+                    let animated = self.morphs.iter().any(|morph| morph.molecule == index);
+                    if !molecule.frames.is_empty() && !animated {
+                        let previous = molecule.frame();
+                        molecule.set_frame(frame % molecule.frames.len());
+                        if molecule.frame() != previous {
+                            moved.push(index);
+                        }
+                    }
+                }
+
+                for morph in &self.morphs {
+                    let positions = morph.positions(self.clock);
+                    self.molecules[morph.molecule].set_positions(&positions);
+                    moved.push(morph.molecule);
+                }
+
+                if !moved.is_empty() {
+                    self.update_hydrogen_bonds_of(&moved);
+                }
             }
             Msg::MouseDown(x, y) => {
-                self.mouse.set_pressed(true);
                 self.mouse.set_pos(*x, *y);
+                self.mouse.set_pressed(true);
             }
             Msg::MouseUp => {
+                if !self.mouse.get_pressed() {
+                    return;
+                }
                 self.mouse.set_pressed(false);
+
+                // A press and release without dragging the camera around is a click
+                let (x, y) = self.mouse.get_press_pos();
+                let (last_x, last_y) = self.mouse.get_pos();
+                if (last_x - x).abs() + (last_y - y).abs() <= CLICK_TOLERANCE {
+                    self.picks.push(PickRequest {
+                        x,
+                        y,
+                        gesture: Gesture::Click,
+                    });
+                }
             }
             Msg::MouseMove(x, y) => {
                 if !self.mouse.get_pressed() {
+                    self.picks
+                        .retain(|request| request.gesture != Gesture::Hover);
+                    self.picks.push(PickRequest {
+                        x: *x,
+                        y: *y,
+                        gesture: Gesture::Hover,
+                    });
                     return;
                 }
 
@@ -112,9 +548,90 @@ impl StateWrapper {
         self.0.msg(msg);
     }
 
+    pub fn set_viewport_size(&mut self, width: u32, height: u32) {
+        self.0.set_viewport_size(width, height);
+    }
+
     pub fn add_molecule(&mut self, molecule: Molecule) {
         self.0.add_molecule(molecule);
     }
+
+    pub fn add_measurement(&mut self, measurement: Measurement) -> Result<(), String> {
+        self.0.add_measurement(measurement)
+    }
+
+    pub fn clear_measurements(&mut self) {
+        self.0.clear_measurements();
+    }
+
+    pub fn change_selection(
+        &mut self,
+        change: SelectionChange,
+        atoms: &[AtomId],
+    ) -> Result<(), String> {
+        self.0.change_selection(change, atoms)
+    }
+
+    pub fn add_label(&mut self, label: Label) -> Result<(), String> {
+        self.0.add_label(label)
+    }
+
+    pub fn label_selection(&mut self, content: LabelContent) -> usize {
+        self.0.label_selection(content)
+    }
+
+    pub fn clear_labels(&mut self) {
+        self.0.clear_labels();
+    }
+
+    pub fn select(&mut self, query: &Query) -> usize {
+        self.0.select(query)
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.0.clear_selection();
+    }
+
+    pub fn invert_selection(&mut self) {
+        self.0.invert_selection();
+    }
+
+    pub fn superpose(
+        &mut self,
+        moving: usize,
+        reference: usize,
+        mapping: Option<&[(usize, usize)]>,
+    ) -> Result<f32, String> {
+        self.0.superpose(moving, reference, mapping)
+    }
+
+    pub fn morph(&mut self, keyframes: &[usize], duration: f32) -> Result<(), String> {
+        self.0.morph(keyframes, duration)
+    }
+
+    pub fn stop_morph(&mut self, index: usize) -> Result<(), String> {
+        self.0.stop_morph(index)
+    }
+
+    pub fn set_hydrogen_bond_pairs(&mut self, pairs: &[(usize, usize)]) -> Result<(), String> {
+        self.0.set_hydrogen_bond_pairs(pairs)
+    }
+
+    pub fn set_representation(
+        &mut self,
+        index: usize,
+        representation: Representation,
+    ) -> Result<(), String> {
+        self.0.set_representation(index, representation)
+    }
+
+    pub fn show_partial_bonds(&mut self, index: usize, show: bool) -> Result<(), String> {
+        self.0.show_partial_bonds(index, show)
+    }
+
+    pub fn take_picks(&mut self) -> Vec<PickRequest> {
+        self.0.take_picks()
+    }
 }
 
 pub struct Store {
@@ -129,12 +646,91 @@ impl Store {
     }
 
     pub fn msg(&mut self, msg: &Msg) {
-        match msg {
-            _ => self.state.msg(msg),
-        }
+        self.state.msg(msg);
+    }
+
+    pub fn set_viewport_size(&mut self, width: u32, height: u32) {
+        self.state.set_viewport_size(width, height);
     }
 
     pub fn add_molecule(&mut self, molecule: Molecule) {
         self.state.add_molecule(molecule);
     }
+
+    pub fn add_measurement(&mut self, measurement: Measurement) -> Result<(), String> {
+        self.state.add_measurement(measurement)
+    }
+
+    pub fn clear_measurements(&mut self) {
+        self.state.clear_measurements();
+    }
+
+    pub fn change_selection(
+        &mut self,
+        change: SelectionChange,
+        atoms: &[AtomId],
+    ) -> Result<(), String> {
+        self.state.change_selection(change, atoms)
+    }
+
+    pub fn add_label(&mut self, label: Label) -> Result<(), String> {
+        self.state.add_label(label)
+    }
+
+    pub fn label_selection(&mut self, content: LabelContent) -> usize {
+        self.state.label_selection(content)
+    }
+
+    pub fn clear_labels(&mut self) {
+        self.state.clear_labels();
+    }
+
+    pub fn select(&mut self, query: &Query) -> usize {
+        self.state.select(query)
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.state.clear_selection();
+    }
+
+    pub fn invert_selection(&mut self) {
+        self.state.invert_selection();
+    }
+
+    pub fn superpose(
+        &mut self,
+        moving: usize,
+        reference: usize,
+        mapping: Option<&[(usize, usize)]>,
+    ) -> Result<f32, String> {
+        self.state.superpose(moving, reference, mapping)
+    }
+
+    pub fn morph(&mut self, keyframes: &[usize], duration: f32) -> Result<(), String> {
+        self.state.morph(keyframes, duration)
+    }
+
+    pub fn stop_morph(&mut self, index: usize) -> Result<(), String> {
+        self.state.stop_morph(index)
+    }
+
+    pub fn set_hydrogen_bond_pairs(&mut self, pairs: &[(usize, usize)]) -> Result<(), String> {
+        self.state.set_hydrogen_bond_pairs(pairs)
+    }
+
+    pub fn set_representation(
+        &mut self,
+        index: usize,
+        representation: Representation,
+    ) -> Result<(), String> {
+        self.state.set_representation(index, representation)
+    }
+
+    pub fn show_partial_bonds(&mut self, index: usize, show: bool) -> Result<(), String> {
+        self.state.show_partial_bonds(index, show)
+    }
+
+    pub fn take_picks(&mut self) -> Vec<PickRequest> {
+        self.state.take_picks()
+    }
 }
@@ -0,0 +1,53 @@
+use webgl_matrix::Vector;
+
+/// An animation which moves a molecule back and forth through the geometries of a
+/// series of keyframes, such as the reactant, transition state and product of a reaction
+pub struct Morph {
+    /// Index into `State::molecules` of the molecule being moved
+    pub molecule: usize,
+    keyframes: Vec<Vec<[f32; 3]>>,
+    /// Time taken to move from one keyframe to the next, in milliseconds
+    duration: f32,
+    /// The clock when the animation started
+    start: f32,
+}
+
+impl Morph {
+    pub fn new(molecule: usize, keyframes: Vec<Vec<[f32; 3]>>, duration: f32, start: f32) -> Self {
+        Self {
+            molecule,
+            keyframes,
+            duration,
+            start,
+        }
+    }
+
+    pub fn first_keyframe(&self) -> &[[f32; 3]] {
+        &self.keyframes[0]
+    }
+
+    /// The positions of the atoms at `clock`. The animation runs to the last keyframe
+    /// and back again, easing in and out of each keyframe so they can be seen.
+    pub fn positions(&self, clock: f32) -> Vec<[f32; 3]> {
+        #![allow(clippy::cast_precision_loss)]
+        #![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+
+        let steps = (self.keyframes.len() - 1) as f32;
+        let phase = ((clock - self.start) / self.duration).rem_euclid(2.0 * steps);
+        let phase = if phase > steps {
+            2.0 * steps - phase
+        } else {
+            phase
+        };
+
+        let index = (phase.floor() as usize).min(self.keyframes.len() - 2);
+        let fraction = phase - index as f32;
+        let eased = fraction * fraction * (3.0 - 2.0 * fraction);
+
+        self.keyframes[index]
+            .iter()
+            .zip(&self.keyframes[index + 1])
+            .map(|(from, to)| from.add(&to.sub(from).scale(eased)))
+            .collect()
+    }
+}
@@ -3,6 +3,9 @@ pub struct Mouse {
     pressed: bool,
     x: i32,
     y: i32,
+    /// Where the mouse was last pressed
+    press_x: i32,
+    press_y: i32,
 }
 
 impl Mouse {
@@ -12,6 +15,10 @@ impl Mouse {
 
     pub fn set_pressed(&mut self, pressed: bool) {
         self.pressed = pressed;
+        if pressed {
+            self.press_x = self.x;
+            self.press_y = self.y;
+        }
     }
 
     pub fn set_pos(&mut self, x: i32, y: i32) {
@@ -22,4 +29,8 @@ impl Mouse {
     pub const fn get_pos(&self) -> (i32, i32) {
         (self.x, self.y)
     }
+
+    pub const fn get_press_pos(&self) -> (i32, i32) {
+        (self.press_x, self.press_y)
+    }
 }
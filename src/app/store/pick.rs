@@ -0,0 +1,28 @@
+use super::AtomId;
+
+/// An atom or bond drawn on the canvas, which the user can point at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    Atom(AtomId),
+    Bond {
+        /// Index into `State::molecules`
+        molecule: usize,
+        /// Index into the `bonds` of the molecule
+        bond: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    Click,
+    Hover,
+}
+
+/// A point of the canvas, in pixels from its top left corner, waiting for the renderer
+/// to find what is drawn there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PickRequest {
+    pub x: i32,
+    pub y: i32,
+    pub gesture: Gesture,
+}
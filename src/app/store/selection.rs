@@ -0,0 +1,64 @@
+use std::collections::BTreeSet;
+
+use super::AtomId;
+use crate::molecule::Molecule;
+
+/// How a list of atoms changes the selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionChange {
+    Add,
+    Remove,
+    /// Select the atoms which were not selected, and deselect the others
+    Toggle,
+}
+
+/// The atoms, across all the molecules, which analyses act on
+#[derive(Debug, Default)]
+pub struct Selection {
+    atoms: BTreeSet<AtomId>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.atoms.is_empty()
+    }
+
+    /// The selected atoms, ordered by molecule then by atom
+    pub fn iter(&self) -> impl Iterator<Item = AtomId> + '_ {
+        self.atoms.iter().copied()
+    }
+
+    pub fn change(&mut self, change: SelectionChange, atoms: &[AtomId]) {
+        for &id in atoms {
+            match change {
+                SelectionChange::Add => {
+                    self.atoms.insert(id);
+                }
+                SelectionChange::Remove => {
+                    self.atoms.remove(&id);
+                }
+                SelectionChange::Toggle => {
+                    if !self.atoms.remove(&id) {
+                        self.atoms.insert(id);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.atoms.clear();
+    }
+
+    /// Select exactly the atoms of `molecules` which were not selected
+    pub fn invert(&mut self, molecules: &[Molecule]) {
+        self.atoms = molecules
+            .iter()
+            .enumerate()
+            .flat_map(|(molecule, atoms)| {
+                (0..atoms.atoms.len()).map(move |atom| AtomId { molecule, atom })
+            })
+            .filter(|id| !self.atoms.contains(id))
+            .collect();
+    }
+}
@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
 use std::rc::Rc;
 
 use wasm_bindgen::prelude::*;
@@ -7,11 +9,22 @@ mod app;
 mod molecule;
 mod render;
 
+/// JavaScript functions called when the user points at an atom or bond
+#[derive(Default)]
+struct Callbacks {
+    atom_click: Option<js_sys::Function>,
+    bond_click: Option<js_sys::Function>,
+    hover: Option<js_sys::Function>,
+    /// What was under the mouse when `hover` was last called
+    hovered: Option<app::Pick>,
+}
+
 #[wasm_bindgen]
 pub struct FerricyanideDisplay {
     app: Rc<app::App>,
     gl: Rc<WebGl2RenderingContext>,
     renderer: render::WebRenderer,
+    callbacks: RefCell<Callbacks>,
 }
 
 #[wasm_bindgen]
@@ -30,33 +43,743 @@ impl FerricyanideDisplay {
 
         let renderer = render::WebRenderer::new(&gl);
 
-        Self { app, gl, renderer }
+        Self {
+            app,
+            gl,
+            renderer,
+            callbacks: RefCell::default(),
+        }
     }
 
-    /// Start our WebGL and initialize the molecules to the value in contents
+    /// Start our WebGL and initialize the molecules to the value in contents.
+    ///
+    /// # Errors
+    ///
+    /// Rejects with an `Error` when the file cannot be read. Problems with the contents
+    /// also carry the `line`, `column` and `reason` of the first one found.
     pub fn add_molecule(&self, contents: Vec<u8>, format: &str) -> Result<(), JsValue> {
-        use molecule::Molecule;
+        use molecule::{Molecule, ParseError};
+
+        let s = String::from_utf8(contents)
+            .map_err(|error| parse_error_to_js(&ParseError::from_utf8(&error)))?;
 
-        let s =
-            String::from_utf8(contents).expect("Found invalid UTF-8 character in Molecule file");
-        if let Ok(molecule) = Molecule::from_string_with_format(&s, format) {
+        let molecules = Molecule::from_string_with_format(&s, format).map_err(|error| {
+            match error.downcast_ref::<ParseError>() {
+                Some(error) => parse_error_to_js(error),
+                None => js_sys::Error::new(&error.to_string()).into(),
+            }
+        })?;
+
+        for molecule in molecules {
             self.app.store.borrow_mut().add_molecule(molecule);
         }
         Ok(())
     }
 
+    /// The hydrogen bonds within each molecule and between the pairs chosen with
+    /// `set_hydrogen_bond_pairs`, as an array of objects with the `donor_molecule`,
+    /// `donor`, `hydrogen`, `acceptor_molecule` and `acceptor` indices, the
+    /// hydrogen-acceptor `distance` and the donor-hydrogen-acceptor `angle`
+    pub fn hydrogen_bonds(&self) -> js_sys::Array {
+        let store = self.app.store.borrow();
+
+        store
+            .state
+            .hydrogen_bonds()
+            .iter()
+            .flat_map(|group| group.bonds.iter().map(move |bond| (group, bond)))
+            .map(|(group, bond)| {
+                js_object(&[
+                    ("donor_molecule", JsValue::from(group.donors as u32)),
+                    ("donor", JsValue::from(bond.donor as u32)),
+                    ("hydrogen", JsValue::from(bond.hydrogen as u32)),
+                    ("acceptor_molecule", JsValue::from(group.acceptors as u32)),
+                    ("acceptor", JsValue::from(bond.acceptor as u32)),
+                    ("distance", JsValue::from(bond.distance)),
+                    ("angle", JsValue::from(bond.angle)),
+                ])
+            })
+            .collect()
+    }
+
+    /// Also find the hydrogen bonds donated by `donors[i]` to `acceptors[i]`, such as
+    /// between a ligand and its protein, instead of the pairs chosen before. Both
+    /// orders of a pair are needed for bonds in either direction. Bonds within a
+    /// molecule are always found.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the arrays differ in length or a molecule does not exist
+    pub fn set_hydrogen_bond_pairs(
+        &self,
+        donors: Vec<u32>,
+        acceptors: Vec<u32>,
+    ) -> Result<(), JsValue> {
+        if donors.len() != acceptors.len() {
+            return Err(js_sys::Error::new("Each donor needs an acceptor").into());
+        }
+
+        let pairs: Vec<(usize, usize)> = donors
+            .into_iter()
+            .zip(acceptors)
+            .map(|(donors, acceptors)| (donors as usize, acceptors as usize))
+            .collect();
+
+        self.app
+            .store
+            .borrow_mut()
+            .set_hydrogen_bond_pairs(&pairs)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// Properties of one molecule, as an object with the Hill `formula`, the
+    /// `molecular_weight`, the `center_of_mass`, the `inertia_tensor`, the
+    /// `principal_moments` with their `principal_axes`, and the `bounding_sphere`
+    /// with its `center` and `radius`.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when there is no molecule at `index`
+    pub fn properties(&self, index: usize) -> Result<JsValue, JsValue> {
+        let store = self.app.store.borrow();
+        let molecule = store
+            .state
+            .molecules()
+            .get(index)
+            .ok_or_else(|| js_sys::Error::new(&format!("No molecule {}", index)))?;
+
+        let vector = |v: [f32; 3]| -> JsValue {
+            v.iter()
+                .copied()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>()
+                .into()
+        };
+        let matrix = |m: [[f32; 3]; 3]| -> JsValue {
+            m.iter()
+                .copied()
+                .map(vector)
+                .collect::<js_sys::Array>()
+                .into()
+        };
+
+        let (moments, axes) = molecule.principal_axes();
+        let (center, radius) = molecule.bounding_sphere();
+
+        Ok(js_object(&[
+            ("formula", JsValue::from_str(&molecule.hill_formula())),
+            (
+                "molecular_weight",
+                JsValue::from(molecule.molecular_weight()),
+            ),
+            ("center_of_mass", vector(molecule.center_of_mass())),
+            ("inertia_tensor", matrix(molecule.inertia_tensor())),
+            ("principal_moments", vector(moments)),
+            ("principal_axes", matrix(axes)),
+            (
+                "bounding_sphere",
+                js_object(&[
+                    ("center", vector(center)),
+                    ("radius", JsValue::from(radius)),
+                ]),
+            ),
+        ]))
+    }
+
+    /// Measure between two, three or four atoms, given as objects with the `molecule`
+    /// and `atom` indices. Two atoms give a distance, three the angle at the middle atom
+    /// and four the dihedral about the middle bond.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when there are not two to four different atoms, or one of them does not
+    /// exist
+    pub fn add_measurement(&self, atoms: &js_sys::Array) -> Result<(), JsValue> {
+        let index = |object: &JsValue, key: &str| {
+            js_sys::Reflect::get(object, &JsValue::from_str(key))
+                .ok()
+                .and_then(|value| js_index(&value))
+                .ok_or_else(|| js_sys::Error::new(&format!("Expected an index for '{}'", key)))
+        };
+
+        let atoms = atoms
+            .iter()
+            .map(|object| {
+                Ok(app::AtomId {
+                    molecule: index(&object, "molecule")?,
+                    atom: index(&object, "atom")?,
+                })
+            })
+            .collect::<Result<Vec<_>, js_sys::Error>>()?;
+
+        let measurement =
+            app::Measurement::new(&atoms).map_err(|reason| js_sys::Error::new(&reason))?;
+
+        self.app
+            .store
+            .borrow_mut()
+            .add_measurement(measurement)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    pub fn clear_measurements(&self) {
+        self.app.store.borrow_mut().clear_measurements();
+    }
+
+    /// The measurements in their current frames, as an array of objects with the
+    /// `kind` (`distance`, `angle` or `dihedral`), the `atoms` as given to
+    /// `add_measurement`, the `value` in Angstroms or degrees and the `label` drawn
+    pub fn measurements(&self) -> js_sys::Array {
+        let store = self.app.store.borrow();
+        let molecules = store.state.molecules();
+
+        store
+            .state
+            .measurements()
+            .iter()
+            .map(|measurement| {
+                let atoms: js_sys::Array = measurement
+                    .atoms()
+                    .iter()
+                    .map(|id| {
+                        js_object(&[
+                            ("molecule", JsValue::from(id.molecule as u32)),
+                            ("atom", JsValue::from(id.atom as u32)),
+                        ])
+                    })
+                    .collect();
+
+                js_object(&[
+                    ("kind", JsValue::from_str(measurement.kind())),
+                    ("atoms", atoms.into()),
+                    ("value", JsValue::from(measurement.value(molecules))),
+                    ("label", JsValue::from_str(&measurement.label(molecules))),
+                ])
+            })
+            .collect()
+    }
+
+    /// Add atoms of a molecule, given by their indices, to the selection
+    ///
+    /// # Errors
+    ///
+    /// Rejects, leaving the selection as it was, when one of the atoms does not exist
+    pub fn select_atoms(&self, molecule: usize, atoms: Vec<u32>) -> Result<(), JsValue> {
+        self.change_selection(app::SelectionChange::Add, molecule, &atoms)
+    }
+
+    /// Remove atoms of a molecule from the selection
+    ///
+    /// # Errors
+    ///
+    /// Rejects, leaving the selection as it was, when one of the atoms does not exist
+    pub fn deselect_atoms(&self, molecule: usize, atoms: Vec<u32>) -> Result<(), JsValue> {
+        self.change_selection(app::SelectionChange::Remove, molecule, &atoms)
+    }
+
+    /// Select the given atoms of a molecule which are not selected, and deselect the
+    /// others
+    ///
+    /// # Errors
+    ///
+    /// Rejects, leaving the selection as it was, when one of the atoms does not exist
+    pub fn toggle_atoms(&self, molecule: usize, atoms: Vec<u32>) -> Result<(), JsValue> {
+        self.change_selection(app::SelectionChange::Toggle, molecule, &atoms)
+    }
+
+    /// Select exactly the atoms matching a query, resolving to how many there are. A
+    /// query combines `all`, `none`, `hydrogen`, `hetero`, `aromatic`, lists of values
+    /// after `element`, `name`, `resname`, `chain` or `type`, and lists of numbers or
+    /// ranges such as `1-20` after `index` (from 0), `resid` or `serial`, with `and`,
+    /// `or`, `not`, parentheses and `within 3.5 of`, which looks only within the same
+    /// molecule. For example `element N and within 3.5 of resname LIG`.
+    ///
+    /// # Errors
+    ///
+    /// Rejects with an `Error` carrying the `column`, `token` and `reason` of the first
+    /// problem found in the query, leaving the selection as it was
+    pub fn select(&self, query: &str) -> Result<u32, JsValue> {
+        let query = query
+            .parse::<molecule::Query>()
+            .map_err(|error| query_error_to_js(&error))?;
+
+        let count = self.app.store.borrow_mut().select(&query);
+        Ok(u32::try_from(count).unwrap_or(u32::MAX))
+    }
+
+    pub fn clear_selection(&self) {
+        self.app.store.borrow_mut().clear_selection();
+    }
+
+    /// Select every atom which is not selected, and deselect the others
+    pub fn invert_selection(&self) {
+        self.app.store.borrow_mut().invert_selection();
+    }
+
+    /// The indices of the selected atoms of a molecule, in ascending order
+    ///
+    /// # Errors
+    ///
+    /// Rejects when there is no molecule at `index`
+    pub fn selection(&self, index: usize) -> Result<Vec<u32>, JsValue> {
+        let store = self.app.store.borrow();
+        if index >= store.state.molecules().len() {
+            return Err(js_sys::Error::new(&format!("No molecule {}", index)).into());
+        }
+
+        Ok(store
+            .state
+            .selection()
+            .iter()
+            .filter(|id| id.molecule == index)
+            .map(|id| id.atom as u32)
+            .collect())
+    }
+
+    /// Label every selected atom with its `name`, `element`, `index` (from 0), `serial`
+    /// or `residue`, resolving to how many labels were added. Atoms without a name are
+    /// labelled with their element.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the content is unknown
+    pub fn label_atoms(&self, content: &str) -> Result<u32, JsValue> {
+        let content = content
+            .parse::<app::LabelContent>()
+            .map_err(|reason| JsValue::from(js_sys::Error::new(&reason)))?;
+
+        Ok(self.app.store.borrow_mut().label_selection(content) as u32)
+    }
+
+    /// Write `text` next to an atom, following it as it moves
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the atom does not exist
+    pub fn add_label(&self, molecule: usize, atom: usize, text: &str) -> Result<(), JsValue> {
+        let label = app::Label::atom(app::AtomId { molecule, atom }, text);
+
+        self.app
+            .store
+            .borrow_mut()
+            .add_label(label)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// Write `text` at a point in space, given as `[x, y, z]` in Angstroms
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the point does not have three coordinates
+    pub fn add_point_label(&self, position: Vec<f32>, text: &str) -> Result<(), JsValue> {
+        let position = match position.as_slice() {
+            &[x, y, z] => [x, y, z],
+            _ => return Err(js_sys::Error::new("A point has three coordinates").into()),
+        };
+
+        self.app
+            .store
+            .borrow_mut()
+            .add_label(app::Label::point(position, text))
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// Remove every label, leaving the values of the measurements
+    pub fn clear_labels(&self) {
+        self.app.store.borrow_mut().clear_labels();
+    }
+
+    /// Rotate and translate the molecule at `moving` onto the one at `reference`,
+    /// resolving to the RMSD of the paired atoms in Angstroms. The `mapping` is an array
+    /// of `[moving_atom, reference_atom]` pairs; without it the atoms are paired by index.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when a molecule does not exist or the atoms cannot be paired
+    pub fn superpose(
+        &self,
+        moving: usize,
+        reference: usize,
+        mapping: Option<js_sys::Array>,
+    ) -> Result<f32, JsValue> {
+        let mapping = mapping.as_ref().map(atom_pairs).transpose()?;
+
+        self.app
+            .store
+            .borrow_mut()
+            .superpose(moving, reference, mapping.as_deref())
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// The RMSD between two molecules as they would be once superposed, without moving
+    /// either. The `mapping` is as for `superpose`.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when a molecule does not exist or the atoms cannot be paired
+    pub fn rmsd(
+        &self,
+        moving: usize,
+        reference: usize,
+        mapping: Option<js_sys::Array>,
+    ) -> Result<f32, JsValue> {
+        let mapping = mapping.as_ref().map(atom_pairs).transpose()?;
+
+        let store = self.app.store.borrow();
+        let molecules = store.state.molecules();
+        let molecule = |index: usize| {
+            molecules
+                .get(index)
+                .ok_or_else(|| js_sys::Error::new(&format!("No molecule {}", index)))
+        };
+
+        molecule(moving)?
+            .superposition(molecule(reference)?, mapping.as_deref())
+            .map(|superposition| superposition.rmsd)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// Animate a molecule through the geometries of others with the same atoms, such as
+    /// the reactant, transition state and product of a reaction, and back again. The
+    /// first molecule is moved, spending `duration` milliseconds between consecutive
+    /// keyframes, while the others stay in the scene as they are. Bonds are found again
+    /// as the atoms move, and a trajectory of the first molecule is paused until the
+    /// animation stops. Keyframes written in different orientations should be
+    /// superposed first.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the molecules do not exist or do not share their atoms
+    pub fn morph(&self, molecules: Vec<u32>, duration: f32) -> Result<(), JsValue> {
+        let keyframes: Vec<usize> = molecules.into_iter().map(|index| index as usize).collect();
+
+        self.app
+            .store
+            .borrow_mut()
+            .morph(&keyframes, duration)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// Stop animating a molecule, putting it back in the geometry of its first keyframe
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the molecule is not being animated
+    pub fn stop_morph(&self, index: usize) -> Result<(), JsValue> {
+        self.app
+            .store
+            .borrow_mut()
+            .stop_morph(index)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// Draw a molecule as a `spacefill` of van der Waals spheres, in `ball-and-stick`,
+    /// as `licorice` sticks or as a `wireframe` of lines
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the molecule does not exist or the representation is unknown
+    pub fn set_representation(&self, index: usize, representation: &str) -> Result<(), JsValue> {
+        let representation = representation
+            .parse()
+            .map_err(|reason: String| js_sys::Error::new(&reason))?;
+
+        self.app
+            .store
+            .borrow_mut()
+            .set_representation(index, representation)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// Draw dashed bonds between the atoms of a molecule which are just too far apart to
+    /// be bonded, such as the bonds being formed and broken in a transition state. They
+    /// are not found until asked for, and are never picked or counted as bonds.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the molecule does not exist
+    pub fn show_partial_bonds(&self, index: usize, show: bool) -> Result<(), JsValue> {
+        self.app
+            .store
+            .borrow_mut()
+            .show_partial_bonds(index, show)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+
+    /// The name of the representation of a molecule, as given to `set_representation`
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the molecule does not exist
+    pub fn representation(&self, index: usize) -> Result<String, JsValue> {
+        let store = self.app.store.borrow();
+
+        store
+            .state
+            .molecules()
+            .get(index)
+            .map(|molecule| molecule.representation.name().to_owned())
+            .ok_or_else(|| js_sys::Error::new(&format!("No molecule {}", index)).into())
+    }
+
+    /// The atom or bond drawn at a point of the canvas, in pixels from its top left
+    /// corner. Atoms are objects with the `kind` `atom` and the `molecule` and `atom`
+    /// indices, while bonds have the `kind` `bond`, the `molecule` and `bond` indices and
+    /// the indices of their two `atoms`. Resolves to `null` when nothing is there.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the scene cannot be read back from the GPU
+    pub fn pick(&self, x: i32, y: i32) -> Result<JsValue, JsValue> {
+        let store = self.app.store.borrow();
+        let target = self.renderer.pick(&self.gl, &store.state, x, y)?;
+
+        Ok(target.map_or(JsValue::NULL, |target| {
+            pick_to_js(&target, store.state.molecules())
+        }))
+    }
+
+    /// Render the scene as the camera sees it into an image of `width` by `height`
+    /// pixels, whatever the size of the canvas, resolving to the bytes of a PNG file.
+    /// The view is widened or narrowed to the shape of the image, and the sky is left
+    /// out with `transparent_background`.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when the image is empty or larger than the GPU can draw, or the scene
+    /// cannot be read back from the GPU
+    pub fn snapshot(
+        &self,
+        width: u32,
+        height: u32,
+        transparent_background: bool,
+    ) -> Result<Vec<u8>, JsValue> {
+        let mut store = self.app.store.borrow_mut();
+
+        store.set_viewport_size(width.max(1), height.max(1));
+        let image = self.renderer.snapshot(
+            &self.gl,
+            &store.state,
+            width,
+            height,
+            transparent_background,
+        );
+
+        #[allow(clippy::cast_sign_loss)]
+        let canvas = (
+            self.gl.drawing_buffer_width() as u32,
+            self.gl.drawing_buffer_height() as u32,
+        );
+        store.set_viewport_size(canvas.0, canvas.1);
+
+        image
+    }
+
+    /// Call `callback` with the atom, as given by `pick`, whenever one is clicked. This
+    /// replaces the previous callback, and passing nothing stops the calls.
+    pub fn on_atom_click(&self, callback: Option<js_sys::Function>) {
+        self.callbacks.borrow_mut().atom_click = callback;
+    }
+
+    /// Call `callback` with the bond, as given by `pick`, whenever one is clicked
+    pub fn on_bond_click(&self, callback: Option<js_sys::Function>) {
+        self.callbacks.borrow_mut().bond_click = callback;
+    }
+
+    /// Call `callback` whenever the mouse moves onto another atom or bond, with the
+    /// atom or bond as given by `pick`, or with `null` as it leaves them
+    pub fn on_hover(&self, callback: Option<js_sys::Function>) {
+        let mut callbacks = self.callbacks.borrow_mut();
+        callbacks.hover = callback;
+        callbacks.hovered = None;
+    }
+
     /// Update our simulation
     pub fn update_time(&self, dt: f32) {
         self.app.store.borrow_mut().msg(&app::Msg::AdvanceClock(dt));
     }
 
-    /// Render the scene. `index.html` will call this once every requestAnimationFrame
-    pub fn render(&mut self) -> Result<(), JsValue> {
+    /// Render the scene. `index.html` will call this once every requestAnimationFrame.
+    /// The atoms and bonds clicked or hovered since the last frame are then picked and
+    /// passed to their callbacks.
+    ///
+    /// # Errors
+    ///
+    /// Rejects when a callback throws
+    pub fn render(&self) -> Result<(), JsValue> {
+        use app::{Gesture, Pick};
+
         self.renderer
-            .render(&self.gl, &self.app.store.borrow().state)
+            .render(&self.gl, &self.app.store.borrow().state)?;
+
+        let requests = self.app.store.borrow_mut().take_picks();
+
+        for request in requests {
+            let wanted = {
+                let callbacks = self.callbacks.borrow();
+                match request.gesture {
+                    Gesture::Click => {
+                        callbacks.atom_click.is_some() || callbacks.bond_click.is_some()
+                    }
+                    Gesture::Hover => callbacks.hover.is_some(),
+                }
+            };
+            if !wanted {
+                continue;
+            }
+
+            let store = self.app.store.borrow();
+            let target = self
+                .renderer
+                .pick(&self.gl, &store.state, request.x, request.y)?;
+            let object = target.map_or(JsValue::NULL, |target| {
+                pick_to_js(&target, store.state.molecules())
+            });
+            drop(store);
+
+            let callback = {
+                let mut callbacks = self.callbacks.borrow_mut();
+                match (request.gesture, target) {
+                    (Gesture::Click, Some(Pick::Atom(_))) => callbacks.atom_click.clone(),
+                    (Gesture::Click, Some(Pick::Bond { .. })) => callbacks.bond_click.clone(),
+                    (Gesture::Click, None) => None,
+                    (Gesture::Hover, _) if callbacks.hovered == target => None,
+                    (Gesture::Hover, _) => {
+                        callbacks.hovered = target;
+                        callbacks.hover.clone()
+                    }
+                }
+            };
+
+            // Nothing is borrowed while the callback runs, so it may call back in
+            if let Some(callback) = callback {
+                callback.call1(&JsValue::NULL, &object)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FerricyanideDisplay {
+    fn change_selection(
+        &self,
+        change: app::SelectionChange,
+        molecule: usize,
+        atoms: &[u32],
+    ) -> Result<(), JsValue> {
+        let atoms: Vec<app::AtomId> = atoms
+            .iter()
+            .map(|&atom| app::AtomId {
+                molecule,
+                atom: atom as usize,
+            })
+            .collect();
+
+        self.app
+            .store
+            .borrow_mut()
+            .change_selection(change, &atoms)
+            .map_err(|reason| js_sys::Error::new(&reason).into())
+    }
+}
+
+/// Set properties on a JavaScript object
+fn set_properties(object: &JsValue, properties: &[(&str, JsValue)]) {
+    for (key, value) in properties {
+        // Setting a property on an object we just created cannot fail
+        let _ = js_sys::Reflect::set(object, &JsValue::from_str(key), value);
+    }
+}
+
+/// Build a plain JavaScript object from its properties
+fn js_object(properties: &[(&str, JsValue)]) -> JsValue {
+    let object = js_sys::Object::new().into();
+    set_properties(&object, properties);
+    object
+}
+
+/// An index given from JavaScript, which must be a whole number
+fn js_index(value: &JsValue) -> Option<usize> {
+    #![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+
+    value
+        .as_f64()
+        .filter(|value| value.fract() == 0.0 && *value >= 0.0)
+        .map(|value| value as usize)
+}
+
+/// Read an array of `[first, second]` atom index pairs
+fn atom_pairs(pairs: &js_sys::Array) -> Result<Vec<(usize, usize)>, js_sys::Error> {
+    pairs
+        .iter()
+        .map(|pair| {
+            let pair = js_sys::Array::from(&pair);
+            match (js_index(&pair.get(0)), js_index(&pair.get(1))) {
+                (Some(first), Some(second)) if pair.length() == 2 => Ok((first, second)),
+                _ => Err(js_sys::Error::new("Expected pairs of atom indices")),
+            }
+        })
+        .collect()
+}
+
+/// An atom or bond as given to the callbacks of `FerricyanideDisplay`
+fn pick_to_js(target: &app::Pick, molecules: &[molecule::Molecule]) -> JsValue {
+    match *target {
+        app::Pick::Atom(id) => js_object(&[
+            ("kind", JsValue::from_str("atom")),
+            ("molecule", JsValue::from(id.molecule as u32)),
+            ("atom", JsValue::from(id.atom as u32)),
+        ]),
+        app::Pick::Bond { molecule, bond } => {
+            let atoms: js_sys::Array = molecules[molecule].bonds[bond]
+                .atoms
+                .iter()
+                .map(|&atom| JsValue::from(atom as u32))
+                .collect();
+
+            js_object(&[
+                ("kind", JsValue::from_str("bond")),
+                ("molecule", JsValue::from(molecule as u32)),
+                ("bond", JsValue::from(bond as u32)),
+                ("atoms", atoms.into()),
+            ])
+        }
     }
 }
 
+/// Convert a `QueryError` into a JavaScript `Error` with the failing token as properties
+fn query_error_to_js(error: &molecule::QueryError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string()).into();
+
+    set_properties(
+        &js_error,
+        &[
+            ("column", JsValue::from(error.column as u32)),
+            ("token", JsValue::from_str(&error.token)),
+            ("reason", JsValue::from_str(&error.reason)),
+        ],
+    );
+
+    js_error
+}
+
+/// Convert a `ParseError` into a JavaScript `Error` with its position as properties
+fn parse_error_to_js(error: &molecule::ParseError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string()).into();
+
+    set_properties(
+        &js_error,
+        &[
+            ("line", JsValue::from(error.line as u32)),
+            ("column", JsValue::from(error.column as u32)),
+            ("reason", JsValue::from_str(&error.reason)),
+        ],
+    );
+
+    js_error
+}
+
 /// # Errors
 ///
 /// This function cannot fail
@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::{build_molecule, Atom, Element, Field, Molecule, ParseError};
+
+enum Token<'a> {
+    Data(&'a str),
+    Loop,
+    Tag(&'a str),
+    Value(&'a str),
+}
+
+/// Splits a CIF file into tags, values and the reserved words we need
+struct Tokenizer<'a> {
+    file: &'a str,
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(file: &'a str) -> Self {
+        Self { file, pos: 0 }
+    }
+
+    /// The line and column of a byte offset of the file
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let before = &self.file[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+        (before.matches('\n').count() + 1, offset - line_start + 1)
+    }
+
+    fn error(&self, offset: usize, reason: impl Into<String>) -> ParseError {
+        let (line, column) = self.position(offset);
+        ParseError::new(line, column, reason)
+    }
+
+    /// Parse a value, such as a coordinate, found at a byte offset of the file. Its line
+    /// and column are only worked out when it cannot be parsed, as that scans the file.
+    fn parse<T: FromStr>(&self, text: &'a str, offset: usize, what: &str) -> Result<T, ParseError> {
+        text.parse().or_else(|_| {
+            let (line, column) = self.position(offset);
+            Field { text, line, column }.parse(what)
+        })
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.pos == 0 || self.file.as_bytes()[self.pos - 1] == b'\n'
+    }
+
+    /// Semicolon text fields run until the next line which starts with a semicolon
+    fn text_field(&mut self) -> Result<&'a str, ParseError> {
+        let start = self.pos + 1;
+        let end = self.file[start..]
+            .find("\n;")
+            .ok_or_else(|| self.error(self.pos, "Unterminated multi-line text field"))?;
+
+        self.pos = start + end + 2;
+        Ok(self.file[start..start + end].trim())
+    }
+
+    /// A quote only closes the value when it is followed by whitespace
+    fn quoted(&mut self, quote: u8) -> Result<&'a str, ParseError> {
+        let bytes = self.file.as_bytes();
+        let start = self.pos + 1;
+        let mut end = start;
+
+        loop {
+            match bytes.get(end) {
+                Some(&b) if b == quote => {
+                    if bytes.get(end + 1).is_none_or(u8::is_ascii_whitespace) {
+                        break;
+                    }
+                }
+                Some(b'\n') | None => return Err(self.error(self.pos, "Unterminated quoted value")),
+                Some(_) => {}
+            }
+            end += 1;
+        }
+
+        self.pos = end + 1;
+        Ok(&self.file[start..end])
+    }
+
+    fn bare(&mut self) -> &'a str {
+        let start = self.pos;
+        let end = self.file[start..]
+            .find(|c: char| c.is_ascii_whitespace())
+            .map_or(self.file.len(), |end| start + end);
+
+        self.pos = end;
+        &self.file[start..end]
+    }
+
+    /// The next token along with its byte offset in the file
+    fn next_token(&mut self) -> Result<Option<(usize, Token<'a>)>, ParseError> {
+        let bytes = self.file.as_bytes();
+
+        loop {
+            match bytes.get(self.pos) {
+                None => return Ok(None),
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(b'#') => {
+                    self.pos = self.file[self.pos..]
+                        .find('\n')
+                        .map_or(self.file.len(), |end| self.pos + end);
+                }
+                Some(b';') if self.at_line_start() => {
+                    let start = self.pos;
+                    return Ok(Some((start, Token::Value(self.text_field()?))));
+                }
+                Some(&quote) if quote == b'\'' || quote == b'"' => {
+                    let start = self.pos + 1;
+                    return Ok(Some((start, Token::Value(self.quoted(quote)?))));
+                }
+                Some(_) => {
+                    let start = self.pos;
+                    let word = self.bare();
+                    let token = if word.starts_with('_') {
+                        Token::Tag(word)
+                    } else if word.eq_ignore_ascii_case("loop_") {
+                        Token::Loop
+                    } else if word
+                        .get(..5)
+                        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("data_"))
+                    {
+                        Token::Data(&word[5..])
+                    } else {
+                        Token::Value(word)
+                    };
+                    return Ok(Some((start, token)));
+                }
+            }
+        }
+    }
+}
+
+/// All items of one category, such as `_atom_site`, stored row by row along with
+/// the byte offset of each value
+#[derive(Default)]
+struct Table<'a> {
+    items: Vec<String>,
+    values: Vec<(usize, &'a str)>,
+}
+
+impl<'a> Table<'a> {
+    fn rows(&self) -> usize {
+        if self.items.is_empty() {
+            0
+        } else {
+            self.values.len() / self.items.len()
+        }
+    }
+
+    fn column(&self, item: &str) -> Option<usize> {
+        self.items.iter().position(|i| i == item)
+    }
+
+    /// Look up an item, treating the unknown (`?`) and inapplicable (`.`) values as missing
+    fn get(&self, row: usize, column: Option<usize>) -> Option<&'a str> {
+        self.get_with_offset(row, column).map(|(_, value)| value)
+    }
+
+    fn get_with_offset(&self, row: usize, column: Option<usize>) -> Option<(usize, &'a str)> {
+        let (offset, value) = *self.values.get(row * self.items.len() + column?)?;
+        match value {
+            "?" | "." => None,
+            _ => Some((offset, value)),
+        }
+    }
+}
+
+/// Split `_category.item` into its lower cased parts
+fn split_tag(tag: &str) -> (String, String) {
+    let tag = tag.to_ascii_lowercase();
+    match tag.find('.') {
+        Some(dot) => (tag[..dot].to_owned(), tag[dot + 1..].to_owned()),
+        None => (tag, String::new()),
+    }
+}
+
+/// Read the categories of the first data block in the file
+fn read_block<'a>(
+    tokens: &mut Tokenizer<'a>,
+) -> Result<(String, HashMap<String, Table<'a>>), ParseError> {
+    let mut name = String::new();
+    let mut tables = HashMap::<String, Table>::new();
+
+    let mut token = tokens.next_token()?;
+    while let Some((offset, current)) = token {
+        token = tokens.next_token()?;
+
+        match current {
+            Token::Data(block) => {
+                if !name.is_empty() || !tables.is_empty() {
+                    break;
+                }
+                name = block.to_owned();
+            }
+            Token::Tag(tag) => {
+                let value = match token {
+                    Some((offset, Token::Value(value))) => (offset, value),
+                    _ => {
+                        let end = offset + tag.len();
+                        return Err(tokens.error(end, format!("Missing value for {}", tag)));
+                    }
+                };
+                token = tokens.next_token()?;
+
+                let (category, item) = split_tag(tag);
+                let table = tables.entry(category).or_default();
+                table.items.push(item);
+                table.values.push(value);
+            }
+            Token::Loop => {
+                let mut table = Table::default();
+                let mut category = String::new();
+
+                while let Some((_, Token::Tag(tag))) = token {
+                    let (loop_category, item) = split_tag(tag);
+                    category = loop_category;
+                    table.items.push(item);
+                    token = tokens.next_token()?;
+                }
+
+                while let Some((offset, Token::Value(value))) = token {
+                    table.values.push((offset, value));
+                    token = tokens.next_token()?;
+                }
+
+                if table.items.is_empty() || table.values.len() % table.items.len() != 0 {
+                    let reason = format!("Incomplete loop for {}", category);
+                    return Err(tokens.error(offset, reason));
+                }
+
+                tables.insert(category, table);
+            }
+            Token::Value(value) => {
+                return Err(tokens.error(offset, format!("Unexpected value {}", value)));
+            }
+        }
+    }
+
+    Ok((name, tables))
+}
+
+/// Identifies an atom the way `_struct_conn` refers to it
+type AtomKey<'a> = (&'a str, &'a str, &'a str, &'a str);
+
+/// Read the connections between the partners of `_struct_conn`, skipping hydrogen bonds
+fn read_connections<'a>(table: &Table<'a>) -> Vec<(AtomKey<'a>, AtomKey<'a>)> {
+    let partner = |prefix: &str| {
+        [
+            table.column(&format!("{}_label_asym_id", prefix)),
+            table.column(&format!("{}_label_comp_id", prefix)),
+            table.column(&format!("{}_auth_seq_id", prefix)),
+            table.column(&format!("{}_label_atom_id", prefix)),
+        ]
+    };
+
+    let first = partner("ptnr1");
+    let second = partner("ptnr2");
+    let kind = table.column("conn_type_id");
+
+    let key = |row: usize, columns: &[Option<usize>; 4]| -> Option<AtomKey<'a>> {
+        Some((
+            table.get(row, columns[0])?,
+            table.get(row, columns[1])?,
+            table.get(row, columns[2])?,
+            table.get(row, columns[3])?,
+        ))
+    };
+
+    (0..table.rows())
+        .filter(|&row| table.get(row, kind) != Some("hydrog"))
+        .filter_map(|row| Some((key(row, &first)?, key(row, &second)?)))
+        .collect()
+}
+
+/// Read a PDBx/mmCIF file. The atoms are taken from `_atom_site`, with each model
+/// becoming a separate `Molecule`, and the covalent and metal coordination bonds
+/// from `_struct_conn` are added to the distance based bonds.
+pub fn read_cif(file: &str) -> Result<Vec<Molecule>, ParseError> {
+    let mut tokens = Tokenizer::new(file);
+    let (name, tables) = read_block(&mut tokens)?;
+
+    let site = match tables.get("_atom_site") {
+        Some(site) => site,
+        None => return Ok(Vec::new()),
+    };
+
+    let column = |items: &[&str]| items.iter().find_map(|item| site.column(item));
+
+    let group = column(&["group_pdb"]);
+    let id = column(&["id"]);
+    let symbol = column(&["type_symbol"]);
+    let atom_name = column(&["auth_atom_id", "label_atom_id"]);
+    let alt_id = column(&["label_alt_id"]);
+    let residue_name = column(&["auth_comp_id", "label_comp_id"]);
+    let chain = column(&["auth_asym_id", "label_asym_id"]);
+    let residue_number = column(&["auth_seq_id", "label_seq_id"]);
+    let x = column(&["cartn_x"]);
+    let y = column(&["cartn_y"]);
+    let z = column(&["cartn_z"]);
+    let occupancy = column(&["occupancy"]);
+    let b_factor = column(&["b_iso_or_equiv"]);
+    let model = column(&["pdbx_pdb_model_num"]);
+    let charge = column(&["pdbx_formal_charge"]);
+
+    let key_columns = [
+        column(&["label_asym_id"]),
+        column(&["label_comp_id"]),
+        column(&["auth_seq_id", "label_seq_id"]),
+        column(&["label_atom_id"]),
+    ];
+
+    let mut models = Vec::<(Vec<Atom>, HashMap<AtomKey, usize>)>::new();
+    let mut current_model = None;
+    let mut first_alt = HashMap::<AtomKey, &str>::new();
+
+    for row in 0..site.rows() {
+        let key = (
+            site.get(row, key_columns[0]).unwrap_or(""),
+            site.get(row, key_columns[1]).unwrap_or(""),
+            site.get(row, key_columns[2]).unwrap_or(""),
+            site.get(row, key_columns[3]).unwrap_or(""),
+        );
+
+        let model_number = site.get(row, model).unwrap_or("");
+        if current_model != Some(model_number) {
+            current_model = Some(model_number);
+            models.push((Vec::new(), HashMap::new()));
+            first_alt.clear();
+        }
+
+        // Only keep the first alternate location of each atom
+        if let Some(alt) = site.get(row, alt_id) {
+            if *first_alt.entry(key).or_insert(alt) != alt {
+                continue;
+            }
+        }
+
+        let coordinate = |column| -> Result<f32, ParseError> {
+            match site.get_with_offset(row, column) {
+                Some((offset, value)) => tokens.parse(value, offset, "a coordinate"),
+                None => {
+                    let (offset, _) = site.values[row * site.items.len()];
+                    Err(tokens.error(offset, "Missing coordinate"))
+                }
+            }
+        };
+
+        let element = Element::from(site.get(row, symbol).unwrap_or(""));
+
+        let mut atom = Atom::new(element, [coordinate(x)?, coordinate(y)?, coordinate(z)?]);
+        atom.serial = site.get(row, id).and_then(|s| s.parse().ok()).unwrap_or(0);
+        atom.name = site.get(row, atom_name).unwrap_or("").to_owned();
+        atom.residue_name = site.get(row, residue_name).unwrap_or("").to_owned();
+        atom.chain = site.get(row, chain).unwrap_or("").to_owned();
+        atom.residue_number = site
+            .get(row, residue_number)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        atom.occupancy = site
+            .get(row, occupancy)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1.0);
+        atom.b_factor = site
+            .get(row, b_factor)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0.0);
+        atom.hetero = site.get(row, group) == Some("HETATM");
+        atom.formal_charge = site
+            .get(row, charge)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+
+        if let Some((atoms, keys)) = models.last_mut() {
+            keys.insert(key, atoms.len());
+            atoms.push(atom);
+        }
+    }
+
+    let connections = tables
+        .get("_struct_conn")
+        .map(read_connections)
+        .unwrap_or_default();
+
+    Ok(models
+        .into_iter()
+        .map(|(atoms, keys)| {
+            let explicit: Vec<(usize, usize)> = connections
+                .iter()
+                .filter_map(|(first, second)| Some((*keys.get(first)?, *keys.get(second)?)))
+                .collect();
+
+            build_molecule(atoms, &explicit, &name)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATOM_SITE: &str = "loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+ATOM 1 C 0.000 0.000 0.000
+ATOM 2 O 1.230 0.000 0.000
+";
+
+    #[test]
+    fn reads_non_ascii_bare_values() {
+        let file = format!("data_test\n_audit_author.name Schrödinger\n{}", ATOM_SITE);
+        let molecules = read_cif(&file).unwrap();
+
+        assert_eq!(molecules.len(), 1);
+        assert_eq!(molecules[0].atoms.len(), 2);
+        assert_eq!(molecules[0].bonds.len(), 1);
+    }
+
+    #[test]
+    fn reports_the_column_of_errors() {
+        let file = format!("data_test\n{}", ATOM_SITE.replace("1.230", "1.2x0"));
+        let error = read_cif(&file).err().unwrap();
+
+        assert_eq!((error.line, error.column), (10, 10));
+    }
+}
@@ -1,58 +1,431 @@
+/// A chemical element, with `Other` standing in for unknown symbols such as dummy atoms.
+/// The discriminant of each element is its atomic number.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Element {
-    Hydrogen,
-    Helium,
-    Lithium,
-    Beryllium,
-    Boron,
-    Carbon,
-    Nitrogen,
-    Oxygen,
-    Fluorine,
-    Neon,
-    Phosphorus,
-    Sulfur,
-    Other,
+    Hydrogen = 1,
+    Helium = 2,
+    Lithium = 3,
+    Beryllium = 4,
+    Boron = 5,
+    Carbon = 6,
+    Nitrogen = 7,
+    Oxygen = 8,
+    Fluorine = 9,
+    Neon = 10,
+    Sodium = 11,
+    Magnesium = 12,
+    Aluminium = 13,
+    Silicon = 14,
+    Phosphorus = 15,
+    Sulfur = 16,
+    Chlorine = 17,
+    Argon = 18,
+    Potassium = 19,
+    Calcium = 20,
+    Scandium = 21,
+    Titanium = 22,
+    Vanadium = 23,
+    Chromium = 24,
+    Manganese = 25,
+    Iron = 26,
+    Cobalt = 27,
+    Nickel = 28,
+    Copper = 29,
+    Zinc = 30,
+    Gallium = 31,
+    Germanium = 32,
+    Arsenic = 33,
+    Selenium = 34,
+    Bromine = 35,
+    Krypton = 36,
+    Rubidium = 37,
+    Strontium = 38,
+    Yttrium = 39,
+    Zirconium = 40,
+    Niobium = 41,
+    Molybdenum = 42,
+    Technetium = 43,
+    Ruthenium = 44,
+    Rhodium = 45,
+    Palladium = 46,
+    Silver = 47,
+    Cadmium = 48,
+    Indium = 49,
+    Tin = 50,
+    Antimony = 51,
+    Tellurium = 52,
+    Iodine = 53,
+    Xenon = 54,
+    Caesium = 55,
+    Barium = 56,
+    Lanthanum = 57,
+    Cerium = 58,
+    Praseodymium = 59,
+    Neodymium = 60,
+    Promethium = 61,
+    Samarium = 62,
+    Europium = 63,
+    Gadolinium = 64,
+    Terbium = 65,
+    Dysprosium = 66,
+    Holmium = 67,
+    Erbium = 68,
+    Thulium = 69,
+    Ytterbium = 70,
+    Lutetium = 71,
+    Hafnium = 72,
+    Tantalum = 73,
+    Tungsten = 74,
+    Rhenium = 75,
+    Osmium = 76,
+    Iridium = 77,
+    Platinum = 78,
+    Gold = 79,
+    Mercury = 80,
+    Thallium = 81,
+    Lead = 82,
+    Bismuth = 83,
+    Polonium = 84,
+    Astatine = 85,
+    Radon = 86,
+    Francium = 87,
+    Radium = 88,
+    Actinium = 89,
+    Thorium = 90,
+    Protactinium = 91,
+    Uranium = 92,
+    Neptunium = 93,
+    Plutonium = 94,
+    Americium = 95,
+    Curium = 96,
+    Berkelium = 97,
+    Californium = 98,
+    Einsteinium = 99,
+    Fermium = 100,
+    Mendelevium = 101,
+    Nobelium = 102,
+    Lawrencium = 103,
+    Rutherfordium = 104,
+    Dubnium = 105,
+    Seaborgium = 106,
+    Bohrium = 107,
+    Hassium = 108,
+    Meitnerium = 109,
+    Darmstadtium = 110,
+    Roentgenium = 111,
+    Copernicium = 112,
+    Nihonium = 113,
+    Flerovium = 114,
+    Moscovium = 115,
+    Livermorium = 116,
+    Tennessine = 117,
+    Oganesson = 118,
+    Other = 0,
 }
 
+/// The constants of one element, indexed by atomic number - 1 in `DATA`
+struct Data {
+    symbol: &'static str,
+    name: &'static str,
+    mass: f32,
+    covalent_radius: f32,
+    vdw_radius: f32,
+    color: u32,
+}
+
+/// Masses are the standard atomic weights, or the mass number of the most stable isotope.
+/// Covalent radii are from Cordero et al. (2008), using the low spin values for Mn, Fe and
+/// Co, and from Pyykkö and Atsumi (2009) past curium. Van der Waals radii are the Bondi and
+/// Mantina et al. values for main group elements and Alvarez (2013) for most metals, with
+/// 2.0 Å where none is known. Colors follow Jmol, except hydrogen which is drawn light gray
+/// so it stands out against a white background.
+#[rustfmt::skip]
+const DATA: [Data; 118] = [
+    Data { symbol: "H", name: "Hydrogen", mass: 1.008, covalent_radius: 0.31, vdw_radius: 1.10, color: 0xD0_D0_D0_FF },
+    Data { symbol: "He", name: "Helium", mass: 4.0026, covalent_radius: 0.28, vdw_radius: 1.40, color: 0xD9_FF_FF_FF },
+    Data { symbol: "Li", name: "Lithium", mass: 6.94, covalent_radius: 1.28, vdw_radius: 1.81, color: 0xCC_80_FF_FF },
+    Data { symbol: "Be", name: "Beryllium", mass: 9.0122, covalent_radius: 0.96, vdw_radius: 1.53, color: 0xC2_FF_00_FF },
+    Data { symbol: "B", name: "Boron", mass: 10.81, covalent_radius: 0.84, vdw_radius: 1.92, color: 0xFF_B5_B5_FF },
+    Data { symbol: "C", name: "Carbon", mass: 12.011, covalent_radius: 0.76, vdw_radius: 1.70, color: 0x90_90_90_FF },
+    Data { symbol: "N", name: "Nitrogen", mass: 14.007, covalent_radius: 0.71, vdw_radius: 1.55, color: 0x30_50_F8_FF },
+    Data { symbol: "O", name: "Oxygen", mass: 15.999, covalent_radius: 0.66, vdw_radius: 1.52, color: 0xFF_0D_0D_FF },
+    Data { symbol: "F", name: "Fluorine", mass: 18.998, covalent_radius: 0.57, vdw_radius: 1.47, color: 0x90_E0_50_FF },
+    Data { symbol: "Ne", name: "Neon", mass: 20.180, covalent_radius: 0.58, vdw_radius: 1.54, color: 0xB3_E3_F5_FF },
+    Data { symbol: "Na", name: "Sodium", mass: 22.990, covalent_radius: 1.66, vdw_radius: 2.27, color: 0xAB_5C_F2_FF },
+    Data { symbol: "Mg", name: "Magnesium", mass: 24.305, covalent_radius: 1.41, vdw_radius: 1.73, color: 0x8A_FF_00_FF },
+    Data { symbol: "Al", name: "Aluminium", mass: 26.982, covalent_radius: 1.21, vdw_radius: 1.84, color: 0xBF_A6_A6_FF },
+    Data { symbol: "Si", name: "Silicon", mass: 28.085, covalent_radius: 1.11, vdw_radius: 2.10, color: 0xF0_C8_A0_FF },
+    Data { symbol: "P", name: "Phosphorus", mass: 30.974, covalent_radius: 1.07, vdw_radius: 1.80, color: 0xFF_80_00_FF },
+    Data { symbol: "S", name: "Sulfur", mass: 32.06, covalent_radius: 1.05, vdw_radius: 1.80, color: 0xFF_FF_30_FF },
+    Data { symbol: "Cl", name: "Chlorine", mass: 35.45, covalent_radius: 1.02, vdw_radius: 1.75, color: 0x1F_F0_1F_FF },
+    Data { symbol: "Ar", name: "Argon", mass: 39.948, covalent_radius: 1.06, vdw_radius: 1.88, color: 0x80_D1_E3_FF },
+    Data { symbol: "K", name: "Potassium", mass: 39.098, covalent_radius: 2.03, vdw_radius: 2.75, color: 0x8F_40_D4_FF },
+    Data { symbol: "Ca", name: "Calcium", mass: 40.078, covalent_radius: 1.76, vdw_radius: 2.31, color: 0x3D_FF_00_FF },
+    Data { symbol: "Sc", name: "Scandium", mass: 44.956, covalent_radius: 1.70, vdw_radius: 2.30, color: 0xE6_E6_E6_FF },
+    Data { symbol: "Ti", name: "Titanium", mass: 47.867, covalent_radius: 1.60, vdw_radius: 2.15, color: 0xBF_C2_C7_FF },
+    Data { symbol: "V", name: "Vanadium", mass: 50.942, covalent_radius: 1.53, vdw_radius: 2.05, color: 0xA6_A6_AB_FF },
+    Data { symbol: "Cr", name: "Chromium", mass: 51.996, covalent_radius: 1.39, vdw_radius: 2.05, color: 0x8A_99_C7_FF },
+    Data { symbol: "Mn", name: "Manganese", mass: 54.938, covalent_radius: 1.39, vdw_radius: 2.05, color: 0x9C_7A_C7_FF },
+    Data { symbol: "Fe", name: "Iron", mass: 55.845, covalent_radius: 1.32, vdw_radius: 2.05, color: 0xE0_66_33_FF },
+    Data { symbol: "Co", name: "Cobalt", mass: 58.933, covalent_radius: 1.26, vdw_radius: 2.00, color: 0xF0_90_A0_FF },
+    Data { symbol: "Ni", name: "Nickel", mass: 58.693, covalent_radius: 1.24, vdw_radius: 2.00, color: 0x50_D0_50_FF },
+    Data { symbol: "Cu", name: "Copper", mass: 63.546, covalent_radius: 1.32, vdw_radius: 2.00, color: 0xC8_80_33_FF },
+    Data { symbol: "Zn", name: "Zinc", mass: 65.38, covalent_radius: 1.22, vdw_radius: 2.10, color: 0x7D_80_B0_FF },
+    Data { symbol: "Ga", name: "Gallium", mass: 69.723, covalent_radius: 1.22, vdw_radius: 1.87, color: 0xC2_8F_8F_FF },
+    Data { symbol: "Ge", name: "Germanium", mass: 72.630, covalent_radius: 1.20, vdw_radius: 2.11, color: 0x66_8F_8F_FF },
+    Data { symbol: "As", name: "Arsenic", mass: 74.922, covalent_radius: 1.19, vdw_radius: 1.85, color: 0xBD_80_E3_FF },
+    Data { symbol: "Se", name: "Selenium", mass: 78.971, covalent_radius: 1.20, vdw_radius: 1.90, color: 0xFF_A1_00_FF },
+    Data { symbol: "Br", name: "Bromine", mass: 79.904, covalent_radius: 1.20, vdw_radius: 1.83, color: 0xA6_29_29_FF },
+    Data { symbol: "Kr", name: "Krypton", mass: 83.798, covalent_radius: 1.16, vdw_radius: 2.02, color: 0x5C_B8_D1_FF },
+    Data { symbol: "Rb", name: "Rubidium", mass: 85.468, covalent_radius: 2.20, vdw_radius: 3.03, color: 0x70_2E_B0_FF },
+    Data { symbol: "Sr", name: "Strontium", mass: 87.62, covalent_radius: 1.95, vdw_radius: 2.49, color: 0x00_FF_00_FF },
+    Data { symbol: "Y", name: "Yttrium", mass: 88.906, covalent_radius: 1.90, vdw_radius: 2.40, color: 0x94_FF_FF_FF },
+    Data { symbol: "Zr", name: "Zirconium", mass: 91.224, covalent_radius: 1.75, vdw_radius: 2.30, color: 0x94_E0_E0_FF },
+    Data { symbol: "Nb", name: "Niobium", mass: 92.906, covalent_radius: 1.64, vdw_radius: 2.15, color: 0x73_C2_C9_FF },
+    Data { symbol: "Mo", name: "Molybdenum", mass: 95.95, covalent_radius: 1.54, vdw_radius: 2.10, color: 0x54_B5_B5_FF },
+    Data { symbol: "Tc", name: "Technetium", mass: 98.0, covalent_radius: 1.47, vdw_radius: 2.05, color: 0x3B_9E_9E_FF },
+    Data { symbol: "Ru", name: "Ruthenium", mass: 101.07, covalent_radius: 1.46, vdw_radius: 2.05, color: 0x24_8F_8F_FF },
+    Data { symbol: "Rh", name: "Rhodium", mass: 102.91, covalent_radius: 1.42, vdw_radius: 2.00, color: 0x0A_7D_8C_FF },
+    Data { symbol: "Pd", name: "Palladium", mass: 106.42, covalent_radius: 1.39, vdw_radius: 2.05, color: 0x00_69_85_FF },
+    Data { symbol: "Ag", name: "Silver", mass: 107.87, covalent_radius: 1.45, vdw_radius: 2.10, color: 0xC0_C0_C0_FF },
+    Data { symbol: "Cd", name: "Cadmium", mass: 112.41, covalent_radius: 1.44, vdw_radius: 2.20, color: 0xFF_D9_8F_FF },
+    Data { symbol: "In", name: "Indium", mass: 114.82, covalent_radius: 1.42, vdw_radius: 2.20, color: 0xA6_75_73_FF },
+    Data { symbol: "Sn", name: "Tin", mass: 118.71, covalent_radius: 1.39, vdw_radius: 1.93, color: 0x66_80_80_FF },
+    Data { symbol: "Sb", name: "Antimony", mass: 121.76, covalent_radius: 1.39, vdw_radius: 2.17, color: 0x9E_63_B5_FF },
+    Data { symbol: "Te", name: "Tellurium", mass: 127.60, covalent_radius: 1.38, vdw_radius: 2.06, color: 0xD4_7A_00_FF },
+    Data { symbol: "I", name: "Iodine", mass: 126.90, covalent_radius: 1.39, vdw_radius: 1.98, color: 0x94_00_94_FF },
+    Data { symbol: "Xe", name: "Xenon", mass: 131.29, covalent_radius: 1.40, vdw_radius: 2.16, color: 0x42_9E_B0_FF },
+    Data { symbol: "Cs", name: "Caesium", mass: 132.91, covalent_radius: 2.44, vdw_radius: 3.43, color: 0x57_17_8F_FF },
+    Data { symbol: "Ba", name: "Barium", mass: 137.33, covalent_radius: 2.15, vdw_radius: 2.68, color: 0x00_C9_00_FF },
+    Data { symbol: "La", name: "Lanthanum", mass: 138.91, covalent_radius: 2.07, vdw_radius: 2.50, color: 0x70_D4_FF_FF },
+    Data { symbol: "Ce", name: "Cerium", mass: 140.12, covalent_radius: 2.04, vdw_radius: 2.48, color: 0xFF_FF_C7_FF },
+    Data { symbol: "Pr", name: "Praseodymium", mass: 140.91, covalent_radius: 2.03, vdw_radius: 2.47, color: 0xD9_FF_C7_FF },
+    Data { symbol: "Nd", name: "Neodymium", mass: 144.24, covalent_radius: 2.01, vdw_radius: 2.45, color: 0xC7_FF_C7_FF },
+    Data { symbol: "Pm", name: "Promethium", mass: 145.0, covalent_radius: 1.99, vdw_radius: 2.43, color: 0xA3_FF_C7_FF },
+    Data { symbol: "Sm", name: "Samarium", mass: 150.36, covalent_radius: 1.98, vdw_radius: 2.42, color: 0x8F_FF_C7_FF },
+    Data { symbol: "Eu", name: "Europium", mass: 151.96, covalent_radius: 1.98, vdw_radius: 2.40, color: 0x61_FF_C7_FF },
+    Data { symbol: "Gd", name: "Gadolinium", mass: 157.25, covalent_radius: 1.96, vdw_radius: 2.38, color: 0x45_FF_C7_FF },
+    Data { symbol: "Tb", name: "Terbium", mass: 158.93, covalent_radius: 1.94, vdw_radius: 2.37, color: 0x30_FF_C7_FF },
+    Data { symbol: "Dy", name: "Dysprosium", mass: 162.50, covalent_radius: 1.92, vdw_radius: 2.35, color: 0x1F_FF_C7_FF },
+    Data { symbol: "Ho", name: "Holmium", mass: 164.93, covalent_radius: 1.92, vdw_radius: 2.33, color: 0x00_FF_9C_FF },
+    Data { symbol: "Er", name: "Erbium", mass: 167.26, covalent_radius: 1.89, vdw_radius: 2.32, color: 0x00_E6_75_FF },
+    Data { symbol: "Tm", name: "Thulium", mass: 168.93, covalent_radius: 1.90, vdw_radius: 2.30, color: 0x00_D4_52_FF },
+    Data { symbol: "Yb", name: "Ytterbium", mass: 173.05, covalent_radius: 1.87, vdw_radius: 2.28, color: 0x00_BF_38_FF },
+    Data { symbol: "Lu", name: "Lutetium", mass: 174.97, covalent_radius: 1.87, vdw_radius: 2.27, color: 0x00_AB_24_FF },
+    Data { symbol: "Hf", name: "Hafnium", mass: 178.49, covalent_radius: 1.75, vdw_radius: 2.25, color: 0x4D_C2_FF_FF },
+    Data { symbol: "Ta", name: "Tantalum", mass: 180.95, covalent_radius: 1.70, vdw_radius: 2.20, color: 0x4D_A6_FF_FF },
+    Data { symbol: "W", name: "Tungsten", mass: 183.84, covalent_radius: 1.62, vdw_radius: 2.10, color: 0x21_94_D6_FF },
+    Data { symbol: "Re", name: "Rhenium", mass: 186.21, covalent_radius: 1.51, vdw_radius: 2.05, color: 0x26_7D_AB_FF },
+    Data { symbol: "Os", name: "Osmium", mass: 190.23, covalent_radius: 1.44, vdw_radius: 2.00, color: 0x26_66_96_FF },
+    Data { symbol: "Ir", name: "Iridium", mass: 192.22, covalent_radius: 1.41, vdw_radius: 2.00, color: 0x17_54_87_FF },
+    Data { symbol: "Pt", name: "Platinum", mass: 195.08, covalent_radius: 1.36, vdw_radius: 2.05, color: 0xD0_D0_E0_FF },
+    Data { symbol: "Au", name: "Gold", mass: 196.97, covalent_radius: 1.36, vdw_radius: 2.10, color: 0xFF_D1_23_FF },
+    Data { symbol: "Hg", name: "Mercury", mass: 200.59, covalent_radius: 1.32, vdw_radius: 2.05, color: 0xB8_B8_D0_FF },
+    Data { symbol: "Tl", name: "Thallium", mass: 204.38, covalent_radius: 1.45, vdw_radius: 1.96, color: 0xA6_54_4D_FF },
+    Data { symbol: "Pb", name: "Lead", mass: 207.2, covalent_radius: 1.46, vdw_radius: 2.02, color: 0x57_59_61_FF },
+    Data { symbol: "Bi", name: "Bismuth", mass: 208.98, covalent_radius: 1.48, vdw_radius: 2.07, color: 0x9E_4F_B5_FF },
+    Data { symbol: "Po", name: "Polonium", mass: 209.0, covalent_radius: 1.40, vdw_radius: 1.97, color: 0xAB_5C_00_FF },
+    Data { symbol: "At", name: "Astatine", mass: 210.0, covalent_radius: 1.50, vdw_radius: 2.02, color: 0x75_4F_45_FF },
+    Data { symbol: "Rn", name: "Radon", mass: 222.0, covalent_radius: 1.50, vdw_radius: 2.20, color: 0x42_82_96_FF },
+    Data { symbol: "Fr", name: "Francium", mass: 223.0, covalent_radius: 2.60, vdw_radius: 3.48, color: 0x42_00_66_FF },
+    Data { symbol: "Ra", name: "Radium", mass: 226.0, covalent_radius: 2.21, vdw_radius: 2.83, color: 0x00_7D_00_FF },
+    Data { symbol: "Ac", name: "Actinium", mass: 227.0, covalent_radius: 2.15, vdw_radius: 2.00, color: 0x70_AB_FA_FF },
+    Data { symbol: "Th", name: "Thorium", mass: 232.04, covalent_radius: 2.06, vdw_radius: 2.40, color: 0x00_BA_FF_FF },
+    Data { symbol: "Pa", name: "Protactinium", mass: 231.04, covalent_radius: 2.00, vdw_radius: 2.00, color: 0x00_A1_FF_FF },
+    Data { symbol: "U", name: "Uranium", mass: 238.03, covalent_radius: 1.96, vdw_radius: 2.30, color: 0x00_8F_FF_FF },
+    Data { symbol: "Np", name: "Neptunium", mass: 237.0, covalent_radius: 1.90, vdw_radius: 2.00, color: 0x00_80_FF_FF },
+    Data { symbol: "Pu", name: "Plutonium", mass: 244.0, covalent_radius: 1.87, vdw_radius: 2.00, color: 0x00_6B_FF_FF },
+    Data { symbol: "Am", name: "Americium", mass: 243.0, covalent_radius: 1.80, vdw_radius: 2.00, color: 0x54_5C_F2_FF },
+    Data { symbol: "Cm", name: "Curium", mass: 247.0, covalent_radius: 1.69, vdw_radius: 2.00, color: 0x78_5C_E3_FF },
+    Data { symbol: "Bk", name: "Berkelium", mass: 247.0, covalent_radius: 1.68, vdw_radius: 2.00, color: 0x8A_4F_E3_FF },
+    Data { symbol: "Cf", name: "Californium", mass: 251.0, covalent_radius: 1.68, vdw_radius: 2.00, color: 0xA1_36_D4_FF },
+    Data { symbol: "Es", name: "Einsteinium", mass: 252.0, covalent_radius: 1.65, vdw_radius: 2.00, color: 0xB3_1F_D4_FF },
+    Data { symbol: "Fm", name: "Fermium", mass: 257.0, covalent_radius: 1.67, vdw_radius: 2.00, color: 0xB3_1F_BA_FF },
+    Data { symbol: "Md", name: "Mendelevium", mass: 258.0, covalent_radius: 1.73, vdw_radius: 2.00, color: 0xB3_0D_A6_FF },
+    Data { symbol: "No", name: "Nobelium", mass: 259.0, covalent_radius: 1.76, vdw_radius: 2.00, color: 0xBD_0D_87_FF },
+    Data { symbol: "Lr", name: "Lawrencium", mass: 266.0, covalent_radius: 1.61, vdw_radius: 2.00, color: 0xC7_00_66_FF },
+    Data { symbol: "Rf", name: "Rutherfordium", mass: 267.0, covalent_radius: 1.57, vdw_radius: 2.00, color: 0xCC_00_59_FF },
+    Data { symbol: "Db", name: "Dubnium", mass: 268.0, covalent_radius: 1.49, vdw_radius: 2.00, color: 0xD1_00_4F_FF },
+    Data { symbol: "Sg", name: "Seaborgium", mass: 269.0, covalent_radius: 1.43, vdw_radius: 2.00, color: 0xD9_00_45_FF },
+    Data { symbol: "Bh", name: "Bohrium", mass: 270.0, covalent_radius: 1.41, vdw_radius: 2.00, color: 0xE0_00_38_FF },
+    Data { symbol: "Hs", name: "Hassium", mass: 277.0, covalent_radius: 1.34, vdw_radius: 2.00, color: 0xE6_00_2E_FF },
+    Data { symbol: "Mt", name: "Meitnerium", mass: 278.0, covalent_radius: 1.29, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Ds", name: "Darmstadtium", mass: 281.0, covalent_radius: 1.28, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Rg", name: "Roentgenium", mass: 282.0, covalent_radius: 1.21, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Cn", name: "Copernicium", mass: 285.0, covalent_radius: 1.22, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Nh", name: "Nihonium", mass: 286.0, covalent_radius: 1.36, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Fl", name: "Flerovium", mass: 289.0, covalent_radius: 1.43, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Mc", name: "Moscovium", mass: 290.0, covalent_radius: 1.62, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Lv", name: "Livermorium", mass: 293.0, covalent_radius: 1.75, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Ts", name: "Tennessine", mass: 294.0, covalent_radius: 1.65, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+    Data { symbol: "Og", name: "Oganesson", mass: 294.0, covalent_radius: 1.57, vdw_radius: 2.00, color: 0xEB_00_26_FF },
+];
+
 impl Element {
-    pub fn covalent_radius(&self) -> f32 {
-        use Element::*;
-        match self {
-            Hydrogen => 0.31,
-            Helium => 0.28,
-            Lithium => 1.28,
-            Beryllium => 0.96,
-            Boron => 0.84,
-            Carbon => 0.76,
-            Nitrogen => 0.71,
-            Oxygen => 0.66,
-            Fluorine => 0.57,
-            Neon => 0.58,
-            Phosphorus => 1.07,
-            Sulfur => 1.05,
-            Other => 2.0,
+    /// Every element, in order of atomic number
+    pub const ALL: [Self; 118] = [
+        Self::Hydrogen,
+        Self::Helium,
+        Self::Lithium,
+        Self::Beryllium,
+        Self::Boron,
+        Self::Carbon,
+        Self::Nitrogen,
+        Self::Oxygen,
+        Self::Fluorine,
+        Self::Neon,
+        Self::Sodium,
+        Self::Magnesium,
+        Self::Aluminium,
+        Self::Silicon,
+        Self::Phosphorus,
+        Self::Sulfur,
+        Self::Chlorine,
+        Self::Argon,
+        Self::Potassium,
+        Self::Calcium,
+        Self::Scandium,
+        Self::Titanium,
+        Self::Vanadium,
+        Self::Chromium,
+        Self::Manganese,
+        Self::Iron,
+        Self::Cobalt,
+        Self::Nickel,
+        Self::Copper,
+        Self::Zinc,
+        Self::Gallium,
+        Self::Germanium,
+        Self::Arsenic,
+        Self::Selenium,
+        Self::Bromine,
+        Self::Krypton,
+        Self::Rubidium,
+        Self::Strontium,
+        Self::Yttrium,
+        Self::Zirconium,
+        Self::Niobium,
+        Self::Molybdenum,
+        Self::Technetium,
+        Self::Ruthenium,
+        Self::Rhodium,
+        Self::Palladium,
+        Self::Silver,
+        Self::Cadmium,
+        Self::Indium,
+        Self::Tin,
+        Self::Antimony,
+        Self::Tellurium,
+        Self::Iodine,
+        Self::Xenon,
+        Self::Caesium,
+        Self::Barium,
+        Self::Lanthanum,
+        Self::Cerium,
+        Self::Praseodymium,
+        Self::Neodymium,
+        Self::Promethium,
+        Self::Samarium,
+        Self::Europium,
+        Self::Gadolinium,
+        Self::Terbium,
+        Self::Dysprosium,
+        Self::Holmium,
+        Self::Erbium,
+        Self::Thulium,
+        Self::Ytterbium,
+        Self::Lutetium,
+        Self::Hafnium,
+        Self::Tantalum,
+        Self::Tungsten,
+        Self::Rhenium,
+        Self::Osmium,
+        Self::Iridium,
+        Self::Platinum,
+        Self::Gold,
+        Self::Mercury,
+        Self::Thallium,
+        Self::Lead,
+        Self::Bismuth,
+        Self::Polonium,
+        Self::Astatine,
+        Self::Radon,
+        Self::Francium,
+        Self::Radium,
+        Self::Actinium,
+        Self::Thorium,
+        Self::Protactinium,
+        Self::Uranium,
+        Self::Neptunium,
+        Self::Plutonium,
+        Self::Americium,
+        Self::Curium,
+        Self::Berkelium,
+        Self::Californium,
+        Self::Einsteinium,
+        Self::Fermium,
+        Self::Mendelevium,
+        Self::Nobelium,
+        Self::Lawrencium,
+        Self::Rutherfordium,
+        Self::Dubnium,
+        Self::Seaborgium,
+        Self::Bohrium,
+        Self::Hassium,
+        Self::Meitnerium,
+        Self::Darmstadtium,
+        Self::Roentgenium,
+        Self::Copernicium,
+        Self::Nihonium,
+        Self::Flerovium,
+        Self::Moscovium,
+        Self::Livermorium,
+        Self::Tennessine,
+        Self::Oganesson,
+    ];
+
+    /// Look up an element by atomic number, returning `Other` when out of range
+    pub fn from_atomic_number(number: u8) -> Self {
+        match number {
+            1..=118 => Self::ALL[number as usize - 1],
+            _ => Self::Other,
         }
     }
 
+    /// The atomic number, which is 0 for `Other`
+    pub const fn atomic_number(self) -> u8 {
+        self as u8
+    }
+
+    fn data(self) -> Option<&'static Data> {
+        DATA.get((self.atomic_number() as usize).checked_sub(1)?)
+    }
+
+    /// The element symbol, such as `Fe`, or `X` for `Other`
+    pub fn symbol(&self) -> &'static str {
+        self.data().map_or("X", |data| data.symbol)
+    }
+
+    /// Atomic mass in daltons
+    pub fn mass(&self) -> f32 {
+        self.data().map_or(0.0, |data| data.mass)
+    }
+
+    /// Covalent radius in Angstroms. Unknown elements take half of the 2.0 Å that
+    /// atoms were once bonded within, less the bond tolerance, so that two of them
+    /// still bond as they used to.
+    pub fn covalent_radius(&self) -> f32 {
+        self.data().map_or(0.775, |data| data.covalent_radius)
+    }
+
+    pub fn vdw_radius(&self) -> f32 {
+        self.data().map_or(2.0, |data| data.vdw_radius)
+    }
+
     pub fn cpk_color(&self) -> [f32; 4] {
         #![allow(clippy::identity_op, clippy::cast_precision_loss)]
 
-        use Element::*;
-        let color: u32 = match self {
-            Hydrogen => 0xD0_D0_D0_FF,
-            Helium => 0xD9_FF_FF_FF,
-            Lithium => 0xCC_80_FF_FF,
-            Beryllium => 0xC2_FF_00_FF,
-            Boron => 0xFF_B5_B5_FF,
-            Carbon => 0x90_90_90_FF,
-            Nitrogen => 0x30_50_F8_FF,
-            Oxygen => 0xFF_0D_0D_FF,
-            Fluorine => 0x90_E0_50_FF,
-            Neon => 0xB3_E3_F5_FF,
-            Phosphorus => 0xFF_80_00_FF,
-            Sulfur => 0xFF_FF_30_FF,
-            Other => 0x00_00_00_FF,
-        };
+        let color = self.data().map_or(0x00_00_00_FF, |data| data.color);
 
         [
             ((color & 0xFF_00_00_00) >> 24) as f32 / 255.0,
@@ -64,22 +437,28 @@ impl Element {
 }
 
 impl From<&str> for Element {
+    /// Parse a symbol or name in any case, such as `FE`, `fe` or `Iron`, or an atomic
+    /// number. Atom labels such as `C1` and charged symbols such as `Fe3+` are read from
+    /// the symbol before their digits and charge.
     fn from(element: &str) -> Self {
-        use Element::*;
-        match element {
-            "H" | "Hydrogen" => Hydrogen,
-            "He" | "Helium" => Helium,
-            "Li" | "Lithium" => Lithium,
-            "Be" | "Beryllium" => Beryllium,
-            "B" | "Boron" => Boron,
-            "C" | "Carbon" => Carbon,
-            "N" | "Nitrogen" => Nitrogen,
-            "O" | "Oxygen" => Oxygen,
-            "F" | "Fluorine" => Fluorine,
-            "Ne" | "Neon" => Neon,
-            "S" | "Sulfur" => Sulfur,
-            "P" | "Phosphorus" => Phosphorus,
-            _ => Other,
+        let element = element.trim();
+
+        if let Ok(number) = element.parse::<u8>() {
+            return Self::from_atomic_number(number);
         }
+
+        let find = |element: &str| {
+            DATA.iter()
+                .position(|data| {
+                    data.symbol.eq_ignore_ascii_case(element)
+                        || data.name.eq_ignore_ascii_case(element)
+                })
+                .map(|index| Self::ALL[index])
+        };
+
+        let symbol = element.trim_end_matches(|c: char| c.is_ascii_digit() || c == '+' || c == '-');
+        find(element)
+            .or_else(|| find(symbol).filter(|_| !symbol.is_empty()))
+            .unwrap_or(Self::Other)
     }
 }
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use webgl_matrix::Vector;
+
+use super::perception::{cell, is_polar};
+use super::{Element, Molecule};
+
+/// Longest distance between the hydrogen and the acceptor
+const MAX_HYDROGEN_DISTANCE: f32 = 2.5;
+
+/// Longest distance between the donor and the acceptor
+const MAX_DONOR_DISTANCE: f32 = 3.5;
+
+/// Smallest donor-hydrogen-acceptor angle, in degrees
+const MIN_ANGLE: f32 = 120.0;
+
+/// A donor-H···acceptor hydrogen bond. The donor and hydrogen index the atoms of the
+/// donating molecule, and the acceptor those of the accepting one.
+#[derive(Debug, Clone, Copy)]
+pub struct HydrogenBond {
+    pub donor: usize,
+    pub hydrogen: usize,
+    pub acceptor: usize,
+    /// Distance between the hydrogen and the acceptor
+    pub distance: f32,
+    /// The donor-hydrogen-acceptor angle, in degrees
+    pub angle: f32,
+}
+
+/// Hydrogens bonded to a N, O or F atom, along with that donor atom
+fn polar_hydrogens(molecule: &Molecule) -> Vec<(usize, usize)> {
+    molecule
+        .bonds
+        .iter()
+        .filter_map(|bond| {
+            let [i, j] = bond.atoms;
+            let element = |atom: usize| molecule.atoms[atom].element;
+
+            match (element(i), element(j)) {
+                (Element::Hydrogen, donor) if is_polar(donor) => Some((j, i)),
+                (donor, Element::Hydrogen) if is_polar(donor) => Some((i, j)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+impl Molecule {
+    /// The hydrogen bonds within this molecule
+    pub fn hydrogen_bonds(&self) -> Vec<HydrogenBond> {
+        self.hydrogen_bonds_to(self)
+    }
+
+    /// The hydrogen bonds donated by this molecule to the N, O and F atoms of
+    /// `acceptors`, which may be this molecule itself. Bonds are found from the
+    /// hydrogen-acceptor and donor-acceptor distances and the angle at the hydrogen.
+    pub fn hydrogen_bonds_to(&self, acceptors: &Self) -> Vec<HydrogenBond> {
+        let mut cells = HashMap::<(i32, i32, i32), Vec<usize>>::new();
+        for (i, atom) in acceptors.atoms.iter().enumerate() {
+            if is_polar(atom.element) {
+                let key = cell(&atom.position, MAX_HYDROGEN_DISTANCE);
+                cells.entry(key).or_default().push(i);
+            }
+        }
+
+        let same = std::ptr::eq(self, acceptors);
+        let mut bonds = Vec::<HydrogenBond>::new();
+
+        for (donor, hydrogen) in polar_hydrogens(self) {
+            let donor_position = self.atoms[donor].position;
+            let position = self.atoms[hydrogen].position;
+            let (x, y, z) = cell(&position, MAX_HYDROGEN_DISTANCE);
+
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let members = match cells.get(&(x + dx, y + dy, z + dz)) {
+                            Some(members) => members,
+                            None => continue,
+                        };
+
+                        for &acceptor in members {
+                            if same && acceptor == donor {
+                                continue;
+                            }
+
+                            let acceptor_position = acceptors.atoms[acceptor].position;
+                            let to_acceptor = acceptor_position.sub(&position);
+                            let to_donor = donor_position.sub(&position);
+
+                            let distance = to_acceptor.mag();
+                            if distance > MAX_HYDROGEN_DISTANCE
+                                || acceptor_position.sub(&donor_position).mag() > MAX_DONOR_DISTANCE
+                            {
+                                continue;
+                            }
+
+                            let cosine = to_acceptor.dot(&to_donor) / (distance * to_donor.mag());
+                            let angle = cosine.clamp(-1.0, 1.0).acos().to_degrees();
+                            if angle < MIN_ANGLE {
+                                continue;
+                            }
+
+                            bonds.push(HydrogenBond {
+                                donor,
+                                hydrogen,
+                                acceptor,
+                                distance,
+                                angle,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        bonds
+    }
+}
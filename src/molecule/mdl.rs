@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use super::{columns, split_fields, Atom, Bond, BondOrder, Element, Field, Molecule, ParseError};
+
+/// Prefix of every line of a V3000 connection table
+const V30: &str = "M  V30 ";
+
+/// Bond types shared by the V2000 and V3000 connection tables. Query types such
+/// as "single or double" cannot be drawn as either, so they are left unknown.
+fn bond_order(kind: &str) -> BondOrder {
+    match kind {
+        "1" => BondOrder::Single,
+        "2" => BondOrder::Double,
+        "3" => BondOrder::Triple,
+        "4" => BondOrder::Aromatic,
+        _ => BondOrder::Unknown,
+    }
+}
+
+/// The V2000 atom block encodes charges as 1 = +3, 2 = +2, 3 = +1, 5 = -1, 6 = -2, 7 = -3
+fn atom_block_charge(code: &str) -> i8 {
+    match code {
+        "1" => 3,
+        "2" => 2,
+        "3" => 1,
+        "5" => -1,
+        "6" => -2,
+        "7" => -3,
+        _ => 0,
+    }
+}
+
+/// Read a V2000 connection table, where `first` is the line number of the header
+fn read_v2000(
+    lines: &[&str],
+    first: usize,
+    atoms: &mut Vec<Atom>,
+    bonds: &mut Vec<Bond>,
+) -> Result<(), ParseError> {
+    let counts = lines[3];
+    let atom_count: usize = Field::fixed(counts, first + 3, 0..3).parse("the number of atoms")?;
+    let bond_count: usize = Field::fixed(counts, first + 3, 3..6).parse("the number of bonds")?;
+
+    let end = first + lines.len();
+    let atom_lines = lines.get(4..4 + atom_count).ok_or_else(|| {
+        ParseError::new(
+            end,
+            1,
+            format!("Incomplete atom block, expected {} atoms", atom_count),
+        )
+    })?;
+    for (i, line) in atom_lines.iter().enumerate() {
+        let number = first + 4 + i;
+        let x: f32 = Field::fixed(line, number, 0..10).parse("a coordinate")?;
+        let y: f32 = Field::fixed(line, number, 10..20).parse("a coordinate")?;
+        let z: f32 = Field::fixed(line, number, 20..30).parse("a coordinate")?;
+
+        let mut atom = Atom::new(Element::from(columns(line, 31..34)), [x, y, z]);
+        atom.serial = u32::try_from(atoms.len() + 1).unwrap_or(u32::MAX);
+        atom.formal_charge = atom_block_charge(columns(line, 36..39));
+        atoms.push(atom);
+    }
+
+    let bond_start = 4 + atom_count;
+    let bond_lines = lines
+        .get(bond_start..bond_start + bond_count)
+        .ok_or_else(|| {
+            ParseError::new(
+                end,
+                1,
+                format!("Incomplete bond block, expected {} bonds", bond_count),
+            )
+        })?;
+    for (i, line) in bond_lines.iter().enumerate() {
+        let number = first + bond_start + i;
+        let atom = |range| -> Result<usize, ParseError> {
+            let field = Field::fixed(line, number, range);
+            match field.parse::<usize>("an atom number")? {
+                index if index >= 1 && index <= atoms.len() => Ok(index - 1),
+                _ => {
+                    let reason = format!("Bond to missing atom {}", field.text);
+                    Err(ParseError::new(number, field.column, reason))
+                }
+            }
+        };
+
+        let bond = Bond::new(atom(0..3)?, atom(3..6)?, bond_order(columns(line, 6..9)));
+        bonds.push(bond);
+    }
+
+    let mut charges_reset = false;
+    for (i, line) in lines.iter().enumerate().skip(bond_start + bond_count) {
+        if line.starts_with("M  END") {
+            break;
+        }
+
+        // Any `M  CHG` line supersedes all of the charges given in the atom block
+        if line.starts_with("M  CHG") {
+            if !charges_reset {
+                atoms.iter_mut().for_each(|atom| atom.formal_charge = 0);
+                charges_reset = true;
+            }
+
+            // Skip `M`, `CHG` and the number of entries
+            let fields = split_fields(line, first + i);
+            for pair in fields[3.min(fields.len())..].chunks(2) {
+                if let [index, charge] = pair {
+                    let index: usize = index.parse("an atom number")?;
+                    let atom = atoms.get_mut(index.wrapping_sub(1)).ok_or_else(|| {
+                        let reason = format!("Charge for missing atom {}", index);
+                        ParseError::new(pair[0].line, pair[0].column, reason)
+                    })?;
+                    atom.formal_charge = charge.parse("a charge")?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Join the `M  V30` lines, which continue onto the next line when ending in `-`.
+/// Each joined line keeps the number of the line it started on.
+fn v3000_lines(lines: &[&str], first: usize) -> Vec<(usize, String)> {
+    let mut joined = Vec::<(usize, String)>::new();
+    let mut continued = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let content = match line.strip_prefix(V30) {
+            Some(content) => content.trim_end(),
+            None => continue,
+        };
+
+        let (content, continues) = match content.strip_suffix('-') {
+            Some(content) => (content, true),
+            None => (content, false),
+        };
+
+        match joined.last_mut() {
+            Some((_, last)) if continued => last.push_str(content),
+            _ => joined.push((first + i, content.to_owned())),
+        }
+
+        continued = continues;
+    }
+
+    joined
+}
+
+/// Read a V3000 connection table, where `first` is the line number of `lines[0]`
+fn read_v3000(
+    lines: &[&str],
+    first: usize,
+    atoms: &mut Vec<Atom>,
+    bonds: &mut Vec<Bond>,
+) -> Result<(), ParseError> {
+    let mut block = String::new();
+    let mut by_index = HashMap::<String, usize>::new();
+
+    for (number, line) in v3000_lines(lines, first) {
+        let mut fields = split_fields(&line, number);
+        for field in &mut fields {
+            field.column += V30.len();
+        }
+
+        match fields.as_slice() {
+            [keyword, name] if keyword.text == "BEGIN" => block = name.text.to_owned(),
+            [keyword, _] if keyword.text == "END" => block.clear(),
+            [index, kind, x, y, z, _map, properties @ ..] if block == "ATOM" => {
+                let position = [
+                    x.parse("a coordinate")?,
+                    y.parse("a coordinate")?,
+                    z.parse("a coordinate")?,
+                ];
+
+                let mut atom = Atom::new(Element::from(kind.text), position);
+                atom.serial = index.text.parse().unwrap_or(0);
+
+                for property in properties {
+                    if let Some(charge) = property.text.strip_prefix("CHG=") {
+                        let field = Field {
+                            text: charge,
+                            column: property.column + 4,
+                            ..*property
+                        };
+                        atom.formal_charge = field.parse("a charge")?;
+                    }
+                }
+
+                by_index.insert(index.text.to_owned(), atoms.len());
+                atoms.push(atom);
+            }
+            [_index, kind, first, second, ..] if block == "BOND" => {
+                let atom = |field: &Field| {
+                    by_index.get(field.text).copied().ok_or_else(|| {
+                        let reason = format!("Bond to missing atom {}", field.text);
+                        ParseError::new(field.line, field.column, reason)
+                    })
+                };
+
+                bonds.push(Bond::new(
+                    atom(first)?,
+                    atom(second)?,
+                    bond_order(kind.text),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a single molfile, using its connection table rather than distances for the bonds.
+/// `first` is the line number of the header line in the file.
+fn read_molfile(lines: &[&str], first: usize) -> Result<Molecule, ParseError> {
+    let counts = lines
+        .get(3)
+        .ok_or_else(|| ParseError::new(first + lines.len(), 1, "Missing counts line"))?;
+
+    let mut atoms = Vec::<Atom>::new();
+    let mut bonds = Vec::<Bond>::new();
+
+    if counts.contains("V3000") {
+        read_v3000(&lines[4..], first + 4, &mut atoms, &mut bonds)?;
+    } else {
+        read_v2000(lines, first, &mut atoms, &mut bonds)?;
+    }
+
+    Ok(Molecule::new(atoms, bonds, lines[0].trim().to_owned()))
+}
+
+/// Read a MDL molfile or SD file. Each record of an SD file, terminated by `$$$$`,
+/// becomes its own `Molecule`. Both the V2000 and V3000 connection tables are supported.
+pub fn read_sdf(file: &str) -> Result<Vec<Molecule>, ParseError> {
+    let mut molecules = Vec::<Molecule>::new();
+    let mut record = Vec::<&str>::new();
+    let mut first = 1;
+
+    // Repeated terminators, and blank lines after the last, leave empty records
+    let blank = |record: &[&str]| record.iter().all(|line| line.trim().is_empty());
+
+    for (index, line) in file.lines().enumerate() {
+        if line.trim_end() == "$$$$" {
+            if !blank(&record) {
+                molecules.push(read_molfile(&record, first)?);
+            }
+            record.clear();
+            first = index + 2;
+        } else {
+            record.push(line);
+        }
+    }
+
+    // A plain molfile, or an SD file missing its last terminator
+    if !blank(&record) {
+        molecules.push(read_molfile(&record, first)?);
+    }
+
+    Ok(molecules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATER: &str = "water
+  test
+
+  3  2  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    0.9572    0.0000    0.0000 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.2400    0.9266    0.0000 H   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0  0  0  0
+  1  3  1  0  0  0  0
+M  END
+";
+
+    #[test]
+    fn skips_empty_records() {
+        let file = format!("{}$$$$\n$$$$\n{}$$$$\n\n\n", WATER, WATER);
+        let molecules = read_sdf(&file).unwrap();
+
+        assert_eq!(molecules.len(), 2);
+        assert!(molecules.iter().all(|molecule| molecule.atoms.len() == 3));
+    }
+
+    #[test]
+    fn reports_the_column_of_errors() {
+        let file = WATER.replace("0.9572", "0.9x72");
+        let error = read_sdf(&file).err().unwrap();
+
+        assert_eq!((error.line, error.column), (6, 1));
+    }
+}
@@ -1,78 +1,302 @@
+use std::collections::HashMap;
 use std::error::Error;
-use std::rc::Rc;
+use std::ops::Range;
+use std::str::FromStr;
 
 pub mod element;
 pub use element::Element;
 
+mod cif;
+pub use cif::read_cif;
+
+mod hbonds;
+pub use hbonds::HydrogenBond;
+
+mod mdl;
+pub use mdl::read_sdf;
+
+mod mol2;
+pub use mol2::read_mol2;
+
+mod pdb;
+pub use pdb::read_pdb;
+
+mod perception;
+use perception::{connect_by_distance, find_partial_bonds, perceive_bond_orders};
+
+mod properties;
+
+mod query;
+pub use query::{Query, QueryError};
+
+mod rings;
+pub use rings::Ring;
+use rings::{find_rings, mark_aromatic};
+
+mod superpose;
+
+mod xyz;
+pub use xyz::read_xyz;
+
 pub struct Atom {
     pub position: [f32; 3],
     pub element: Element,
+    /// Atom name, such as `CA` for an alpha carbon
+    pub name: String,
+    /// Serial number given by the file, used to resolve explicit connectivity
+    pub serial: u32,
+    pub residue_name: String,
+    pub residue_number: i32,
+    pub chain: String,
+    pub occupancy: f32,
+    pub b_factor: f32,
+    /// True for `HETATM` records
+    pub hetero: bool,
+    pub formal_charge: i8,
+    pub partial_charge: f32,
+    /// Force field or SYBYL atom type, such as `C.ar`
+    pub atom_type: String,
+}
+
+impl Atom {
+    /// Create an atom with no biological context
+    pub fn new(element: Element, position: [f32; 3]) -> Self {
+        Self {
+            position,
+            element,
+            name: String::new(),
+            serial: 0,
+            residue_name: String::new(),
+            residue_number: 0,
+            chain: String::new(),
+            occupancy: 1.0,
+            b_factor: 0.0,
+            hetero: false,
+            formal_charge: 0,
+            partial_charge: 0.0,
+            atom_type: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BondOrder {
+    Single,
+    Double,
+    Triple,
+    Aromatic,
+    /// The C-N bond of an amide, which has partial double bond character
+    Amide,
+    /// The order could not be determined
+    Unknown,
+}
+
+pub struct Bond {
+    /// Indices into `Molecule::atoms`
+    pub atoms: [usize; 2],
+    pub order: BondOrder,
+}
+
+impl Bond {
+    pub const fn new(first: usize, second: usize, order: BondOrder) -> Self {
+        Self {
+            atoms: [first, second],
+            order,
+        }
+    }
+}
+
+/// Per-atom values which have no place on `Atom`, such as the forces of extended XYZ files
+#[derive(Clone)]
+pub struct AtomProperty {
+    pub name: String,
+    /// Number of values for each atom, such as 3 for a force vector
+    pub columns: usize,
+    /// The values of every atom, one atom after another
+    pub values: Vec<f32>,
+}
+
+/// Everything which can change between the frames of a trajectory
+#[derive(Clone, Default)]
+pub struct Frame {
+    pub positions: Vec<[f32; 3]>,
+    pub lattice: Option<[[f32; 3]; 3]>,
+    pub info: HashMap<String, String>,
+    pub properties: Vec<AtomProperty>,
+}
+
+/// How the atoms and bonds of a molecule are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Representation {
+    /// Spheres of the van der Waals radius, without bonds
+    Spacefill,
+    /// Small spheres joined by thinner cylinders, with multiple bonds drawn apart
+    #[default]
+    BallAndStick,
+    /// Cylinders of a single radius, with spheres of that radius rounding the joints
+    Licorice,
+    /// Lines between bonded atoms, and a small cross on atoms without bonds
+    Wireframe,
+}
+
+impl Representation {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Spacefill => "spacefill",
+            Self::BallAndStick => "ball-and-stick",
+            Self::Licorice => "licorice",
+            Self::Wireframe => "wireframe",
+        }
+    }
+}
+
+impl FromStr for Representation {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "spacefill" | "vdw" => Ok(Self::Spacefill),
+            "ball-and-stick" | "ball_and_stick" => Ok(Self::BallAndStick),
+            "licorice" => Ok(Self::Licorice),
+            "wireframe" | "lines" => Ok(Self::Wireframe),
+            _ => Err(format!("Unknown representation {}", name)),
+        }
+    }
 }
 
 pub struct Molecule {
-    pub atoms: Vec<Rc<Atom>>,
-    pub bonds: Vec<[Rc<Atom>; 2]>,
+    pub atoms: Vec<Atom>,
+    pub bonds: Vec<Bond>,
+    /// The smallest set of smallest rings
+    pub rings: Vec<Ring>,
+    /// Atoms just beyond bonding distance, as with bonds being formed or broken in a
+    /// transition state. Only found while `show_partial_bonds` is on.
+    pub partial_bonds: Vec<[usize; 2]>,
+    show_partial_bonds: bool,
+    /// Title given by the file, such as the comment line of an XYZ file, but not shown
+    /// anywhere yet
+    #[allow(dead_code)]
     pub name: String,
+    /// Unit cell vectors of a periodic system
+    pub lattice: Option<[[f32; 3]; 3]>,
+    /// Key/value pairs describing the whole structure, such as its energy
+    pub info: HashMap<String, String>,
+    pub properties: Vec<AtomProperty>,
+    /// Every frame of a trajectory, empty for a single structure
+    pub frames: Vec<Frame>,
+    pub representation: Representation,
+    frame: usize,
 }
 
-#[inline]
-pub fn unwrap_abort<T>(o: Option<T>) -> T {
-    use std::process;
-    match o {
-        Some(t) => t,
-        None => process::abort(),
+/// Infer the bond orders when none of them are known, then find the rings. Aromatic
+/// rings override the alternating orders of inferred bonds.
+fn perceive_structure(atoms: &[Atom], bonds: &mut [Bond]) -> Vec<Ring> {
+    let inferred = bonds.iter().all(|bond| bond.order == BondOrder::Unknown);
+    if inferred {
+        perceive_bond_orders(atoms, bonds);
     }
+
+    let rings = find_rings(atoms, bonds);
+    if inferred {
+        mark_aromatic(&rings, bonds);
+    }
+
+    rings
 }
 
-pub fn read_xyz(file: &str) -> Result<Molecule, Box<dyn Error>> {
-    use webgl_matrix::Vector;
+/// Combine the distance based bonds with the index pairs given explicitly by a file
+fn build_molecule(atoms: Vec<Atom>, explicit: &[(usize, usize)], name: &str) -> Molecule {
+    let mut pairs = connect_by_distance(&atoms);
 
-    let mut atoms = Vec::<Rc<Atom>>::new();
-    let mut name = String::new();
+    for &(i, j) in explicit {
+        if i != j {
+            pairs.push((i.min(j), i.max(j)));
+        }
+    }
 
-    let mut total_lines: usize = 0;
+    pairs.sort_unstable();
+    pairs.dedup();
 
-    for (line_count, line) in file.lines().enumerate() {
-        match line_count {
-            0 => total_lines = line.trim_end().parse().unwrap(),
-            1 => name = line.to_owned(),
-            _ => {
-                let line_split: Vec<&str> = line.split_whitespace().collect();
-                /*
-                if line_split.len() < 4 {
-                    return Error(std::error::Error::new("Invalid line"))
-                }*/
-                let element = unwrap_abort(line_split.get(0)).to_owned();
-                let x: f32 = unwrap_abort(line_split.get(1)).parse()?;
-                let y: f32 = unwrap_abort(line_split.get(2)).parse()?;
-                let z: f32 = unwrap_abort(line_split.get(3)).parse()?;
-
-                atoms.push(Rc::new(Atom {
-                    position: [x, y, z],
-                    element: Element::from(element),
-                }))
-            }
+    let bonds = pairs
+        .into_iter()
+        .map(|(i, j)| Bond::new(i, j, BondOrder::Unknown))
+        .collect();
+
+    Molecule::new(atoms, bonds, name.to_owned())
+}
+
+/// Copy a per-atom `charges` property, as written by extended XYZ files, onto the atoms
+fn apply_charges(atoms: &mut [Atom], properties: &[AtomProperty]) {
+    let charges = properties
+        .iter()
+        .find(|property| property.name == "charges" && property.columns == 1);
+
+    if let Some(charges) = charges {
+        for (atom, charge) in atoms.iter_mut().zip(&charges.values) {
+            atom.partial_charge = *charge;
         }
+    }
+}
+
+/// Read a fixed-width field, tolerating lines which are truncated before `range.end`
+fn columns(line: &str, range: Range<usize>) -> &str {
+    let end = range.end.min(line.len());
+    line.get(range.start.min(end)..end).unwrap_or("").trim()
+}
+
+/// A piece of a line, remembering where it was found for error messages
+#[derive(Clone, Copy)]
+struct Field<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
 
-        if total_lines == atoms.len() {
-            break;
+impl<'a> Field<'a> {
+    /// A fixed-width field, see `columns`
+    fn fixed(line: &'a str, line_number: usize, range: Range<usize>) -> Self {
+        Self {
+            text: columns(line, range.clone()),
+            line: line_number,
+            column: range.start + 1,
         }
     }
 
-    let mut bonds = Vec::<[Rc<Atom>; 2]>::new();
+    fn parse<T: FromStr>(&self, what: &str) -> Result<T, ParseError> {
+        self.text.parse().map_err(|_| {
+            ParseError::new(
+                self.line,
+                self.column,
+                format!("Expected {}, found '{}'", what, self.text),
+            )
+        })
+    }
+}
 
-    for i in 0..atoms.len() {
-        for j in (i + 1)..atoms.len() {
-            let atom_1 = unwrap_abort(atoms.get(i));
-            let atom_2 = unwrap_abort(atoms.get(j));
-            let dist_vec = atom_1.position.sub(&atom_2.position);
-            if dist_vec.mag() < 2.0 {
-                bonds.push([atom_1.clone(), atom_2.clone()]);
+/// Split a line on whitespace, keeping the position of every field
+fn split_fields(line: &str, line_number: usize) -> Vec<Field<'_>> {
+    let mut fields = Vec::<Field>::new();
+    let mut start = None;
+
+    for (i, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(begin), true) => {
+                fields.push(Field {
+                    text: &line[begin..i],
+                    line: line_number,
+                    column: begin + 1,
+                });
+                start = None;
             }
+            _ => {}
         }
     }
 
-    Ok(Molecule { atoms, bonds, name })
+    fields
 }
 
 #[derive(Debug)]
@@ -88,7 +312,173 @@ impl std::fmt::Display for UnsupportedFormat {
 
 impl std::error::Error for UnsupportedFormat {}
 
+/// A problem with the contents of a molecule file
+#[derive(Debug)]
+pub struct ParseError {
+    /// Line of the file, starting at 1
+    pub line: usize,
+    /// Column of the line, starting at 1
+    pub column: usize,
+    pub reason: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, reason: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            reason: reason.into(),
+        }
+    }
+
+    /// A value which should have followed the end of `line`
+    fn missing(line_number: usize, line: &str, what: &str) -> Self {
+        Self::new(
+            line_number,
+            line.trim_end().len() + 1,
+            format!("Missing {}", what),
+        )
+    }
+
+    /// Locate the first invalid byte of a file which is not UTF-8
+    pub fn from_utf8(error: &std::string::FromUtf8Error) -> Self {
+        let valid = &error.as_bytes()[..error.utf8_error().valid_up_to()];
+        let line_start = valid
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |newline| newline + 1);
+
+        Self::new(
+            valid.iter().filter(|&&b| b == b'\n').count() + 1,
+            valid.len() - line_start + 1,
+            "Invalid UTF-8 character",
+        )
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.reason
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 impl Molecule {
+    /// Create a molecule, inferring the bond orders when none of them are known
+    pub fn new(atoms: Vec<Atom>, mut bonds: Vec<Bond>, name: String) -> Self {
+        let rings = perceive_structure(&atoms, &mut bonds);
+
+        Self {
+            atoms,
+            bonds,
+            rings,
+            partial_bonds: Vec::new(),
+            show_partial_bonds: false,
+            name,
+            lattice: None,
+            info: HashMap::new(),
+            properties: Vec::new(),
+            frames: Vec::new(),
+            representation: Representation::default(),
+            frame: 0,
+        }
+    }
+
+    pub fn property(&self, name: &str) -> Option<&AtomProperty> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    /// The indices of the atoms bonded to each atom
+    pub fn neighbors(&self) -> Vec<Vec<usize>> {
+        let mut neighbors = vec![Vec::new(); self.atoms.len()];
+        for bond in &self.bonds {
+            let [i, j] = bond.atoms;
+            neighbors[i].push(j);
+            neighbors[j].push(i);
+        }
+
+        neighbors
+    }
+
+    /// Find the partial bonds, and keep finding them as the atoms move, or forget them
+    pub fn show_partial_bonds(&mut self, show: bool) {
+        self.show_partial_bonds = show;
+        self.partial_bonds = if show {
+            find_partial_bonds(&self.atoms, &self.bonds)
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// The trajectory frame currently stored in the atom positions
+    pub const fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Move the atoms to the positions of a trajectory frame. Trajectories come from
+    /// formats without connectivity, so the bonds are found again for the new positions.
+    pub fn set_frame(&mut self, frame: usize) {
+        let data = match self.frames.get(frame) {
+            Some(data) if frame != self.frame => data,
+            _ => return,
+        };
+
+        for (atom, position) in self.atoms.iter_mut().zip(&data.positions) {
+            atom.position = *position;
+        }
+
+        self.lattice = data.lattice;
+        self.info = data.info.clone();
+        self.properties = data.properties.clone();
+        apply_charges(&mut self.atoms, &self.properties);
+
+        self.reconnect(connect_by_distance(&self.atoms));
+        self.frame = frame;
+    }
+
+    /// Move the atoms to new positions, such as those between the keyframes of an
+    /// animation. The bond orders and rings are only found again when an atom pair
+    /// crosses the bonding distance, since most steps barely move the atoms.
+    pub fn set_positions(&mut self, positions: &[[f32; 3]]) {
+        for (atom, position) in self.atoms.iter_mut().zip(positions) {
+            atom.position = *position;
+        }
+
+        let pairs = connect_by_distance(&self.atoms);
+        let changed = pairs.len() != self.bonds.len()
+            || pairs
+                .iter()
+                .zip(&self.bonds)
+                .any(|(&(i, j), bond)| bond.atoms != [i, j]);
+
+        if changed {
+            self.reconnect(pairs);
+        } else if self.show_partial_bonds {
+            self.partial_bonds = find_partial_bonds(&self.atoms, &self.bonds);
+        }
+    }
+
+    /// Replace the bonds with the distance based `pairs`, then find their orders and
+    /// the rings again
+    fn reconnect(&mut self, pairs: Vec<(usize, usize)>) {
+        self.bonds = pairs
+            .into_iter()
+            .map(|(i, j)| Bond::new(i, j, BondOrder::Unknown))
+            .collect();
+        self.rings = perceive_structure(&self.atoms, &mut self.bonds);
+
+        if self.show_partial_bonds {
+            self.partial_bonds = find_partial_bonds(&self.atoms, &self.bonds);
+        }
+    }
+
     pub fn center(&self) -> [f32; 3] {
         #![allow(clippy::cast_precision_loss)]
         use webgl_matrix::Vector;
@@ -105,12 +495,31 @@ impl Molecule {
             .scale(1.0 / self.atoms.len() as f32)
     }
 
-    pub fn from_string_with_format(contents: &str, format: &str) -> Result<Self, Box<dyn Error>> {
-        match format {
-            "xyz" => read_xyz(contents),
-            _ => Err(Box::new(UnsupportedFormat {
-                format: format.to_owned(),
-            })),
+    /// Parse every molecule stored in `contents`. Formats which hold several models,
+    /// such as PDB files with `MODEL` records, return one `Molecule` per model.
+    /// The `xyz` format also reads the key/value comment lines of extended XYZ files.
+    pub fn from_string_with_format(
+        contents: &str,
+        format: &str,
+    ) -> Result<Vec<Self>, Box<dyn Error>> {
+        let molecules = match format {
+            "xyz" | "extxyz" => read_xyz(contents)?,
+            "pdb" => read_pdb(contents)?,
+            "cif" | "mmcif" => read_cif(contents)?,
+            "mol" | "sdf" | "sd" => read_sdf(contents)?,
+            "mol2" => read_mol2(contents)?,
+            _ => {
+                return Err(Box::new(UnsupportedFormat {
+                    format: format.to_owned(),
+                }))
+            }
+        };
+
+        // Usually the wrong format, or an error page served instead of the file
+        if molecules.iter().all(|molecule| molecule.atoms.is_empty()) {
+            return Err(Box::new(ParseError::new(1, 1, "No atoms found")));
         }
+
+        Ok(molecules)
     }
 }
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use super::{split_fields, Atom, Bond, BondOrder, Element, Field, Molecule, ParseError};
+
+fn bond_order(kind: &str) -> BondOrder {
+    match kind {
+        "1" => BondOrder::Single,
+        "2" => BondOrder::Double,
+        "3" => BondOrder::Triple,
+        "ar" => BondOrder::Aromatic,
+        "am" => BondOrder::Amide,
+        _ => BondOrder::Unknown,
+    }
+}
+
+/// Substructure names usually combine the residue name and number, such as `ALA12`
+fn split_substructure_name(name: &str) -> (String, Option<i32>) {
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (residue, number) = name.split_at(name.len() - digits);
+
+    match number.parse() {
+        Ok(number) if !residue.is_empty() => (residue.to_owned(), Some(number)),
+        _ => (name.to_owned(), None),
+    }
+}
+
+/// A molecule being assembled from its `@<TRIPOS>` sections
+#[derive(Default)]
+struct Record {
+    name: String,
+    atoms: Vec<Atom>,
+    bonds: Vec<Bond>,
+    by_id: HashMap<String, usize>,
+    /// The substructure ID of each atom, resolved to a chain once all sections are read
+    substructures: Vec<String>,
+    chains: HashMap<String, String>,
+}
+
+impl Record {
+    fn read_atom(&mut self, line: &str, number: usize) -> Result<(), ParseError> {
+        let fields = split_fields(line, number);
+        let (id, name, x, y, z, atom_type) = match fields.as_slice() {
+            [id, name, x, y, z, atom_type, ..] => (id, name, x, y, z, atom_type),
+            _ => return Err(ParseError::missing(number, line, "atom fields")),
+        };
+
+        // Types are the element followed by the hybridization, such as `N.am`
+        let symbol = atom_type.text.split('.').next().unwrap_or("");
+        let position = [
+            x.parse("a coordinate")?,
+            y.parse("a coordinate")?,
+            z.parse("a coordinate")?,
+        ];
+
+        let mut atom = Atom::new(Element::from(symbol), position);
+        atom.serial = id.text.parse().unwrap_or(0);
+        atom.name = name.text.to_owned();
+        atom.atom_type = atom_type.text.to_owned();
+
+        if let Some(substructure) = fields.get(7) {
+            let (residue, number) = split_substructure_name(substructure.text);
+            atom.residue_name = residue;
+            atom.residue_number = number
+                .or_else(|| fields.get(6)?.text.parse().ok())
+                .unwrap_or(0);
+        }
+
+        if let Some(charge) = fields.get(8) {
+            atom.partial_charge = charge.parse("a partial charge")?;
+        }
+
+        self.by_id.insert(id.text.to_owned(), self.atoms.len());
+        self.substructures
+            .push(fields.get(6).map_or("", |field| field.text).to_owned());
+        self.atoms.push(atom);
+
+        Ok(())
+    }
+
+    fn read_bond(&mut self, line: &str, number: usize) -> Result<(), ParseError> {
+        let fields = split_fields(line, number);
+        let (origin, target, kind) = match fields.as_slice() {
+            [_id, origin, target, kind, ..] => (origin, target, kind),
+            _ => return Err(ParseError::missing(number, line, "bond fields")),
+        };
+
+        // Explicitly not connected
+        if kind.text == "nc" {
+            return Ok(());
+        }
+
+        let atom = |field: &Field| {
+            self.by_id.get(field.text).copied().ok_or_else(|| {
+                let reason = format!("Bond to missing atom {}", field.text);
+                ParseError::new(field.line, field.column, reason)
+            })
+        };
+
+        let bond = Bond::new(atom(origin)?, atom(target)?, bond_order(kind.text));
+        self.bonds.push(bond);
+
+        Ok(())
+    }
+
+    fn read_substructure(&mut self, line: &str) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if let (Some(id), Some(chain)) = (fields.first(), fields.get(5)) {
+            // Unset fields are written as `****`
+            if *chain != "****" {
+                self.chains.insert((*id).to_owned(), (*chain).to_owned());
+            }
+        }
+    }
+
+    fn finish(mut self) -> Molecule {
+        for (atom, substructure) in self.atoms.iter_mut().zip(&self.substructures) {
+            if let Some(chain) = self.chains.get(substructure) {
+                atom.chain = chain.clone();
+            }
+        }
+
+        Molecule::new(self.atoms, self.bonds, self.name)
+    }
+}
+
+/// Read a Tripos MOL2 file. Every `@<TRIPOS>MOLECULE` section, such as each pose of
+/// a docking run, becomes its own `Molecule`. The SYBYL atom types and partial charges
+/// are kept on each `Atom`.
+pub fn read_mol2(file: &str) -> Result<Vec<Molecule>, ParseError> {
+    let mut molecules = Vec::<Molecule>::new();
+    let mut record: Option<Record> = None;
+    let mut section = "";
+    let mut section_line = 0;
+
+    for (index, line) in file.lines().enumerate() {
+        if let Some(name) = line.trim().strip_prefix("@<TRIPOS>") {
+            section = name;
+            section_line = 0;
+
+            if section == "MOLECULE" {
+                molecules.extend(record.take().map(Record::finish));
+                record = Some(Record::default());
+            }
+            continue;
+        }
+
+        let current = match record.as_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+
+        // The name is always the line after the header, even when it is blank
+        if section == "MOLECULE" && section_line == 0 {
+            current.name = line.trim().to_owned();
+            section_line += 1;
+            continue;
+        }
+
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        match section {
+            "ATOM" => current.read_atom(line, index + 1)?,
+            "BOND" => current.read_bond(line, index + 1)?,
+            "SUBSTRUCTURE" => current.read_substructure(line),
+            _ => {}
+        }
+
+        section_line += 1;
+    }
+
+    molecules.extend(record.map(Record::finish));
+
+    Ok(molecules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATER: &str = "@<TRIPOS>MOLECULE
+
+ 2 1 0 0 0
+SMALL
+NO_CHARGES
+
+@<TRIPOS>ATOM
+      1 O1          0.0000    0.0000    0.0000 O.3     1  HOH1        0.0000
+      2 H1          0.9572    0.0000    0.0000 H       1  HOH1        0.0000
+@<TRIPOS>BOND
+     1     1     2    1
+";
+
+    #[test]
+    fn reads_an_empty_name() {
+        let molecules = read_mol2(WATER).unwrap();
+
+        assert_eq!(molecules.len(), 1);
+        assert_eq!(molecules[0].name, "");
+        assert_eq!(molecules[0].atoms.len(), 2);
+        assert_eq!(molecules[0].bonds.len(), 1);
+    }
+
+    #[test]
+    fn reports_the_column_of_errors() {
+        let file = WATER.replace("0.9572", "0.9x72");
+        let error = read_mol2(&file).err().unwrap();
+
+        assert_eq!((error.line, error.column), (9, 21));
+    }
+}
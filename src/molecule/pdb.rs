@@ -0,0 +1,171 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{build_molecule, columns, Atom, Element, Field, Molecule, ParseError};
+
+/// Older files leave the element columns blank, so fall back on the atom name.
+/// Names of standard residues start with a single letter element, while two letter
+/// elements (e.g. `FE`) are only expected left-justified in `HETATM` records.
+fn guess_element(name_field: &str, hetero: bool) -> Element {
+    let two_letters = hetero && name_field.starts_with(|c: char| c.is_ascii_alphabetic());
+
+    let symbol: String = name_field
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .take(if two_letters { 2 } else { 1 })
+        .collect();
+
+    Element::from(symbol.as_str())
+}
+
+fn read_atom(line: &str, number: usize) -> Result<Atom, ParseError> {
+    let hetero = columns(line, 0..6) == "HETATM";
+
+    let element = match columns(line, 76..78) {
+        "" => guess_element(line.get(12..16).unwrap_or(""), hetero),
+        symbol => Element::from(symbol),
+    };
+
+    let x: f32 = Field::fixed(line, number, 30..38).parse("a coordinate")?;
+    let y: f32 = Field::fixed(line, number, 38..46).parse("a coordinate")?;
+    let z: f32 = Field::fixed(line, number, 46..54).parse("a coordinate")?;
+
+    let mut atom = Atom::new(element, [x, y, z]);
+
+    // Serial numbers above 99999 use the hybrid-36 encoding, which we do not resolve
+    atom.serial = columns(line, 6..11).parse().unwrap_or(0);
+    atom.name = columns(line, 12..16).to_owned();
+    atom.residue_name = columns(line, 17..20).to_owned();
+    atom.chain = columns(line, 21..22).to_owned();
+    atom.residue_number = columns(line, 22..26).parse().unwrap_or(0);
+    atom.occupancy = columns(line, 54..60).parse().unwrap_or(1.0);
+    atom.b_factor = columns(line, 60..66).parse().unwrap_or(0.0);
+    atom.hetero = hetero;
+
+    // Charges are written with the sign last, such as `2+`
+    let charge = columns(line, 78..80);
+    if let (Some(magnitude), Some(sign)) = (charge.get(..1), charge.get(1..)) {
+        let magnitude: i8 = magnitude.parse().unwrap_or(0);
+        atom.formal_charge = if sign == "-" { -magnitude } else { magnitude };
+    }
+
+    Ok(atom)
+}
+
+fn read_conect(line: &str, number: usize, links: &mut Vec<(u32, u32)>) -> Result<(), ParseError> {
+    let origin: u32 = Field::fixed(line, number, 6..11).parse("a serial number")?;
+
+    for range in [11..16, 16..21, 21..26, 26..31].iter() {
+        let target = Field::fixed(line, number, range.clone());
+        if !target.text.is_empty() {
+            links.push((origin, target.parse("a serial number")?));
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve the serial numbers of the `CONECT` records to atom indices
+fn resolve_links(atoms: &[Atom], links: &[(u32, u32)]) -> Vec<(usize, usize)> {
+    let by_serial: HashMap<u32, usize> = atoms
+        .iter()
+        .enumerate()
+        .filter(|(_, atom)| atom.serial != 0)
+        .map(|(i, atom)| (atom.serial, i))
+        .collect();
+
+    links
+        .iter()
+        .filter_map(|(origin, target)| Some((*by_serial.get(origin)?, *by_serial.get(target)?)))
+        .collect()
+}
+
+/// Read a Protein Data Bank file. Every `MODEL` becomes a separate `Molecule`, and the
+/// `CONECT` records are applied to each of them. Only the first alternate location of
+/// each atom is kept.
+pub fn read_pdb(file: &str) -> Result<Vec<Molecule>, ParseError> {
+    let mut name = String::new();
+    let mut models = Vec::<Vec<Atom>>::new();
+    let mut atoms = Vec::<Atom>::new();
+    let mut links = Vec::<(u32, u32)>::new();
+    let mut alternates = HashSet::<String>::new();
+
+    for (index, line) in file.lines().enumerate() {
+        let number = index + 1;
+
+        match columns(line, 0..6) {
+            "HEADER" => name = columns(line, 62..66).to_owned(),
+            "MODEL" | "ENDMDL" if !atoms.is_empty() => {
+                models.push(std::mem::take(&mut atoms));
+                alternates.clear();
+            }
+            "ATOM" | "HETATM" => {
+                // The atom name and residue identify an atom without its alternate location
+                let alternate = columns(line, 12..16).to_owned() + columns(line, 17..27);
+                if !columns(line, 16..17).is_empty() && !alternates.insert(alternate) {
+                    continue;
+                }
+
+                atoms.push(read_atom(line, number)?);
+            }
+            "CONECT" => read_conect(line, number, &mut links)?,
+            "END" => break,
+            _ => {}
+        }
+    }
+
+    if !atoms.is_empty() {
+        models.push(atoms);
+    }
+
+    Ok(models
+        .into_iter()
+        .map(|atoms| {
+            let explicit = resolve_links(&atoms, &links);
+            build_molecule(atoms, &explicit, &name)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATER: &str = "\
+HETATM    1  O   HOH A   1       0.000   0.000   0.000  1.00  0.00           O
+HETATM    2  H1 AHOH A   1       0.957   0.000   0.000  1.00  0.00           H
+HETATM    3  H1 BHOH A   1       0.900   0.100   0.000  1.00  0.00           H
+HETATM    4  H2  HOH A   1      -0.240   0.927   0.000  1.00  0.00           H
+";
+
+    fn error(file: &str) -> (usize, usize) {
+        let error = read_pdb(file).err().unwrap();
+        (error.line, error.column)
+    }
+
+    #[test]
+    fn reads_models() {
+        let file = format!(
+            "MODEL        1\n{}ENDMDL\nMODEL        2\n{}ENDMDL\nCONECT    1    2    4\nEND\n{}",
+            WATER, WATER, WATER
+        );
+        let molecules = read_pdb(&file).unwrap();
+
+        assert_eq!(molecules.len(), 2);
+        for molecule in &molecules {
+            let serials: Vec<u32> = molecule.atoms.iter().map(|atom| atom.serial).collect();
+            assert_eq!(serials, [1, 2, 4]);
+            assert_eq!(molecule.bonds.len(), 2);
+            assert!(molecule.atoms.iter().all(|atom| atom.hetero));
+        }
+    }
+
+    #[test]
+    fn reports_the_column_of_errors() {
+        let file = WATER.replace("0.927", "0.9x7");
+        assert_eq!(error(&file), (4, 39));
+
+        let file = format!("{}CONECT    1    x\n", WATER);
+        assert_eq!(error(&file), (5, 12));
+    }
+}
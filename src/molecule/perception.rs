@@ -0,0 +1,414 @@
+use std::collections::{BTreeSet, HashMap};
+
+use webgl_matrix::Vector;
+
+use super::{Atom, Bond, BondOrder, Element};
+
+/// Added to the sum of the covalent radii of two atoms to decide whether they are bonded
+pub const BOND_TOLERANCE: f32 = 0.45;
+
+/// Atoms up to this much further apart than a bond are partially bonded
+pub const PARTIAL_BOND_WINDOW: f32 = 0.6;
+
+/// Atoms closer than this are overlapping copies, such as disordered sites, not bonded
+const MIN_BOND_LENGTH: f32 = 0.4;
+
+/// Index of the cubic cell holding a position
+pub fn cell(position: &[f32; 3], size: f32) -> (i32, i32, i32) {
+    #![allow(clippy::cast_possible_truncation)]
+
+    (
+        (position[0] / size).floor() as i32,
+        (position[1] / size).floor() as i32,
+        (position[2] / size).floor() as i32,
+    )
+}
+
+/// Find the index pairs, and distance, of all atoms closer than the sum of their
+/// covalent radii plus `reach`. Atoms are sorted into cells as wide as the longest
+/// such distance, so only the 27 cells around each atom need to be searched.
+pub fn close_pairs(atoms: &[Atom], reach: f32) -> Vec<(usize, usize, f32)> {
+    let max_radius = atoms
+        .iter()
+        .map(|atom| atom.element.covalent_radius())
+        .fold(0.0, f32::max);
+    let size = 2.0 * max_radius + reach;
+
+    let mut cells = HashMap::<(i32, i32, i32), Vec<usize>>::new();
+    for (i, atom) in atoms.iter().enumerate() {
+        cells.entry(cell(&atom.position, size)).or_default().push(i);
+    }
+
+    let mut pairs = Vec::<(usize, usize, f32)>::new();
+
+    for (&(x, y, z), members) in &cells {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let neighbors = match cells.get(&(x + dx, y + dy, z + dz)) {
+                        Some(neighbors) => neighbors,
+                        None => continue,
+                    };
+
+                    for &i in members {
+                        for &j in neighbors.iter().filter(|&&j| j > i) {
+                            let first = &atoms[i];
+                            let second = &atoms[j];
+                            let max_length = first.element.covalent_radius()
+                                + second.element.covalent_radius()
+                                + reach;
+
+                            let length = first.position.sub(&second.position).mag();
+                            if length < max_length {
+                                pairs.push((i, j, length));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pairs.sort_unstable_by_key(|&(i, j, _)| (i, j));
+    pairs
+}
+
+/// Find the index pairs of all atoms closer than the sum of their covalent radii plus
+/// `BOND_TOLERANCE`
+pub fn connect_by_distance(atoms: &[Atom]) -> Vec<(usize, usize)> {
+    close_pairs(atoms, BOND_TOLERANCE)
+        .into_iter()
+        .filter(|&(_, _, length)| length > MIN_BOND_LENGTH)
+        .map(|(i, j, _)| (i, j))
+        .collect()
+}
+
+/// Whether an atom can donate or accept a hydrogen bond
+pub fn is_polar(element: Element) -> bool {
+    matches!(
+        element,
+        Element::Nitrogen | Element::Oxygen | Element::Fluorine
+    )
+}
+
+/// Find the atoms which are too far apart to be bonded, but by less than
+/// `PARTIAL_BOND_WINDOW`, as with bonds forming or breaking in a transition state.
+/// Atoms two bonds apart are always this close, so they are skipped, as are hydrogens
+/// on N, O or F near an acceptor, which are left to hydrogen bond detection.
+pub fn find_partial_bonds(atoms: &[Atom], bonds: &[Bond]) -> Vec<[usize; 2]> {
+    let mut neighbors = vec![Vec::<usize>::new(); atoms.len()];
+    for bond in bonds {
+        let [i, j] = bond.atoms;
+        neighbors[i].push(j);
+        neighbors[j].push(i);
+    }
+
+    let polar_hydrogen = |h: usize, other: usize| {
+        atoms[h].element == Element::Hydrogen
+            && is_polar(atoms[other].element)
+            && neighbors[h].iter().any(|&n| is_polar(atoms[n].element))
+    };
+
+    close_pairs(atoms, BOND_TOLERANCE + PARTIAL_BOND_WINDOW)
+        .into_iter()
+        .filter(|&(i, j, length)| {
+            let bonded = atoms[i].element.covalent_radius()
+                + atoms[j].element.covalent_radius()
+                + BOND_TOLERANCE;
+
+            length >= bonded
+                && !neighbors[i].contains(&j)
+                && !neighbors[i].iter().any(|n| neighbors[j].contains(n))
+                && !polar_hydrogen(i, j)
+                && !polar_hydrogen(j, i)
+        })
+        .map(|(i, j, _)| [i, j])
+        .collect()
+}
+
+/// Bonds shorter than this fraction of the sum of the covalent radii may be double
+const DOUBLE_BOND_RATIO: f32 = 0.95;
+
+/// Bonds shorter than this fraction of the sum of the covalent radii may be triple
+const TRIPLE_BOND_RATIO: f32 = 0.85;
+
+/// Second row atoms whose bond angles average below this are sp3 hybridized
+const SP3_ANGLE: f32 = 115.0;
+
+/// Atoms with two neighbours at more than this angle are sp hybridized
+const SP_ANGLE: f32 = 155.0;
+
+/// The usual valences of the elements whose bond orders can be inferred, lowest first.
+/// Bonds to any other element, such as a metal, are left single.
+fn valences(element: Element) -> &'static [i32] {
+    use Element::*;
+    match element {
+        Hydrogen | Fluorine | Chlorine | Bromine | Iodine => &[1],
+        Boron => &[3],
+        Carbon | Silicon | Germanium => &[4],
+        Nitrogen => &[3],
+        Phosphorus | Arsenic => &[3, 5],
+        Oxygen => &[2],
+        Sulfur | Selenium | Tellurium => &[2, 4, 6],
+        _ => &[],
+    }
+}
+
+/// The bonds an atom can still make beyond its single bonds to `degree` neighbours
+fn free_valence(atom: &Atom, degree: usize) -> i32 {
+    use Element::*;
+
+    // Cations of N, O, P and S gain a bond, such as in ammonium, while carbanions
+    // and carbocations both lose one
+    let charge = i32::from(atom.formal_charge);
+    let adjust = match atom.element {
+        Boron | Carbon | Silicon | Germanium => -charge.abs(),
+        _ => charge,
+    };
+
+    let degree = degree as i32;
+    valences(atom.element)
+        .iter()
+        .map(|valence| valence + adjust)
+        .find(|&valence| valence >= degree)
+        .map_or(0, |valence| valence - degree)
+}
+
+/// The angle between two bonds of `center`, in degrees
+fn angle(atoms: &[Atom], center: usize, first: usize, second: usize) -> f32 {
+    let u = atoms[first].position.sub(&atoms[center].position);
+    let v = atoms[second].position.sub(&atoms[center].position);
+    let cosine = u.dot(&v) / (u.mag() * v.mag());
+
+    cosine.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// The mean angle between all pairs of bonds to `center`
+fn mean_angle(atoms: &[Atom], center: usize, neighbors: &[usize]) -> Option<f32> {
+    let mut sum = 0.0;
+    let mut count = 0.0;
+
+    for (i, &first) in neighbors.iter().enumerate() {
+        for &second in &neighbors[i + 1..] {
+            sum += angle(atoms, center, first, second);
+            count += 1.0;
+        }
+    }
+
+    if count > 0.0 {
+        Some(sum / count)
+    } else {
+        None
+    }
+}
+
+/// The length of a bond relative to the sum of the covalent radii of its atoms
+fn length_ratio(atoms: &[Atom], bond: &Bond) -> f32 {
+    let [i, j] = bond.atoms;
+    let length = atoms[i].position.sub(&atoms[j].position).mag();
+
+    length / (atoms[i].element.covalent_radius() + atoms[j].element.covalent_radius())
+}
+
+/// Infer the orders of bonds found by distance. Each atom is given its free valence,
+/// which is zero for sp3 centres, and the bonds short enough to be multiple are then
+/// assigned so that no free valence is left over, starting with the atoms which have
+/// a single choice.
+pub fn perceive_bond_orders(atoms: &[Atom], bonds: &mut [Bond]) {
+    let organic = |i: usize| !valences(atoms[i].element).is_empty();
+
+    let mut neighbors = vec![Vec::<usize>::new(); atoms.len()];
+    for bond in bonds.iter_mut() {
+        let [i, j] = bond.atoms;
+        bond.order = BondOrder::Single;
+
+        if organic(i) && organic(j) {
+            neighbors[i].push(j);
+            neighbors[j].push(i);
+        }
+    }
+
+    let mut free: Vec<i32> = atoms
+        .iter()
+        .enumerate()
+        .map(|(i, atom)| {
+            use Element::*;
+
+            let second_row = matches!(atom.element, Boron | Carbon | Nitrogen | Oxygen);
+            let sp3 = mean_angle(atoms, i, &neighbors[i]).is_some_and(|mean| mean < SP3_ANGLE);
+
+            if second_row && sp3 {
+                0
+            } else {
+                free_valence(atom, neighbors[i].len())
+            }
+        })
+        .collect();
+
+    // Bonds which could be multiple, for each atom as (neighbour, bond index)
+    let mut candidates = vec![Vec::<(usize, usize)>::new(); atoms.len()];
+    for (index, bond) in bonds.iter().enumerate() {
+        let [i, j] = bond.atoms;
+        if free[i] > 0 && free[j] > 0 && length_ratio(atoms, bond) < DOUBLE_BOND_RATIO {
+            candidates[i].push((j, index));
+            candidates[j].push((i, index));
+        }
+    }
+
+    let linear = |i: usize| match neighbors[i].as_slice() {
+        [_] => true,
+        [first, second] => angle(atoms, i, *first, *second) > SP_ANGLE,
+        _ => false,
+    };
+
+    for bond in bonds.iter_mut() {
+        let [i, j] = bond.atoms;
+        if free[i] >= 2
+            && free[j] >= 2
+            && linear(i)
+            && linear(j)
+            && length_ratio(atoms, bond) < TRIPLE_BOND_RATIO
+        {
+            bond.order = BondOrder::Triple;
+            free[i] -= 2;
+            free[j] -= 2;
+        }
+    }
+
+    let open = |free: &[i32], bonds: &[Bond], i: usize| -> Vec<(usize, usize)> {
+        candidates[i]
+            .iter()
+            .copied()
+            .filter(|&(j, index)| free[j] > 0 && bonds[index].order == BondOrder::Single)
+            .collect()
+    };
+
+    // Atoms with free valence left, ranked by how many bonds they could still make
+    // double. Only the atoms around a bond made double need to be ranked again.
+    let mut ranked = BTreeSet::<(usize, usize)>::new();
+    let mut ranks = vec![0; atoms.len()];
+    let mut changed: Vec<usize> = (0..atoms.len()).collect();
+
+    let mut queue: Vec<usize> = (0..atoms.len()).filter(|&i| free[i] > 0).collect();
+    loop {
+        while let Some(i) = queue.pop() {
+            let options = open(&free, bonds, i);
+            if free[i] <= 0 || options.is_empty() || options.len() > free[i] as usize {
+                continue;
+            }
+
+            // Every remaining choice of this atom must be a double bond
+            for (j, index) in options {
+                bonds[index].order = BondOrder::Double;
+                free[i] -= 1;
+                free[j] -= 1;
+                queue.extend(candidates[j].iter().map(|&(k, _)| k));
+                changed.extend(candidates[j].iter().map(|&(k, _)| k));
+                changed.push(j);
+            }
+            changed.push(i);
+        }
+
+        for k in changed.drain(..) {
+            ranked.remove(&(ranks[k], k));
+            ranks[k] = if free[k] > 0 {
+                open(&free, bonds, k).len()
+            } else {
+                0
+            };
+            if ranks[k] > 0 {
+                ranked.insert((ranks[k], k));
+            }
+        }
+
+        // Break the tie in a ring or chain by picking the most constrained atom,
+        // and its shortest bond to the most constrained neighbour
+        let i = match ranked.iter().next() {
+            Some(&(_, i)) => i,
+            None => break,
+        };
+
+        let best = open(&free, bonds, i)
+            .into_iter()
+            .min_by(|&(j, a), &(k, b)| {
+                ranks[j].cmp(&ranks[k]).then(
+                    length_ratio(atoms, &bonds[a])
+                        .partial_cmp(&length_ratio(atoms, &bonds[b]))
+                        .unwrap_or(std::cmp::Ordering::Equal),
+                )
+            });
+
+        if let Some((j, index)) = best {
+            bonds[index].order = BondOrder::Double;
+            free[i] -= 1;
+            free[j] -= 1;
+            queue.extend(candidates[i].iter().map(|&(k, _)| k));
+            queue.extend(candidates[j].iter().map(|&(k, _)| k));
+            changed.extend(candidates[i].iter().map(|&(k, _)| k));
+            changed.extend(candidates[j].iter().map(|&(k, _)| k));
+            changed.extend([i, j]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Carbon atoms scattered through a box, from a fixed pseudo-random sequence
+    fn scattered(count: usize, size: f32) -> Vec<Atom> {
+        #![allow(clippy::cast_precision_loss)]
+
+        let mut state = 12345_u32;
+        let mut next = || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 8) as f32 / (1 << 24) as f32 * size - size / 2.0
+        };
+
+        (0..count)
+            .map(|_| Atom::new(Element::Carbon, [next(), next(), next()]))
+            .collect()
+    }
+
+    #[test]
+    fn close_pairs_match_every_pair() {
+        let atoms = scattered(300, 12.0);
+        let reach = BOND_TOLERANCE;
+
+        let mut expected = Vec::new();
+        for i in 0..atoms.len() {
+            for j in i + 1..atoms.len() {
+                let length = atoms[i].position.sub(&atoms[j].position).mag();
+                let max_length = 2.0 * Element::Carbon.covalent_radius() + reach;
+                if length < max_length {
+                    expected.push((i, j));
+                }
+            }
+        }
+
+        let found: Vec<(usize, usize)> = close_pairs(&atoms, reach)
+            .into_iter()
+            .map(|(i, j, _)| (i, j))
+            .collect();
+
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn close_pairs_use_the_covalent_radii() {
+        let atoms = vec![
+            Atom::new(Element::Hydrogen, [0.0, 0.0, 0.0]),
+            Atom::new(Element::Hydrogen, [0.9, 0.0, 0.0]),
+            Atom::new(Element::Sulfur, [2.3, 0.0, 0.0]),
+            Atom::new(Element::Hydrogen, [-1.5, 0.0, 0.0]),
+        ];
+
+        let pairs = close_pairs(&atoms, BOND_TOLERANCE);
+
+        assert_eq!(pairs.len(), 2);
+        assert_eq!((pairs[0].0, pairs[0].1), (0, 1));
+        assert!((pairs[0].2 - 0.9).abs() < 1e-6);
+        assert_eq!((pairs[1].0, pairs[1].1), (1, 2));
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use webgl_matrix::Vector;
+
+use super::{Element, Molecule};
+
+/// Sweeps of the Jacobi eigenvalue method, which converges well before this for the
+/// small matrices used here
+const JACOBI_SWEEPS: usize = 50;
+
+/// Find the eigenvalues, in ascending order, and the unit eigenvectors of a symmetric
+/// matrix by the cyclic Jacobi method
+pub fn symmetric_eigen<const N: usize>(matrix: &[[f64; N]; N]) -> ([f64; N], [[f64; N]; N]) {
+    #![allow(clippy::many_single_char_names)]
+
+    let mut a = *matrix;
+    let mut v = [[0.0; N]; N];
+    for (i, row) in v.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+
+    for _ in 0..JACOBI_SWEEPS {
+        let off_diagonal: f64 = (0..N)
+            .flat_map(|p| ((p + 1)..N).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q].abs())
+            .sum();
+        if off_diagonal < 1e-12 {
+            break;
+        }
+
+        for p in 0..N {
+            for q in (p + 1)..N {
+                if a[p][q].abs() < 1e-15 {
+                    continue;
+                }
+
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                // Rotate the columns p and q, then the rows, zeroing a[p][q]
+                for row in a.iter_mut().chain(&mut v) {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = c * kp - s * kq;
+                    row[q] = s * kp + c * kq;
+                }
+                let (row_p, row_q) = (a[p], a[q]);
+                for k in 0..N {
+                    a[p][k] = c * row_p[k] - s * row_q[k];
+                    a[q][k] = s * row_p[k] + c * row_q[k];
+                }
+            }
+        }
+    }
+
+    let mut order = [0; N];
+    for (i, index) in order.iter_mut().enumerate() {
+        *index = i;
+    }
+    order.sort_by(|&i, &j| a[i][i].total_cmp(&a[j][j]));
+
+    let values = order.map(|i| a[i][i]);
+    let vectors = order.map(|i| {
+        let mut vector = [0.0; N];
+        for (k, value) in vector.iter_mut().enumerate() {
+            *value = v[k][i];
+        }
+        vector
+    });
+
+    (values, vectors)
+}
+
+impl Molecule {
+    /// The chemical formula in Hill order: carbon, then hydrogen, then the other elements
+    /// alphabetically. Without carbon, every element is alphabetical.
+    pub fn hill_formula(&self) -> String {
+        let mut counts = BTreeMap::<&str, usize>::new();
+        for atom in self
+            .atoms
+            .iter()
+            .filter(|atom| atom.element != Element::Other)
+        {
+            *counts.entry(atom.element.symbol()).or_default() += 1;
+        }
+
+        let first: &[&str] = if counts.contains_key("C") {
+            &["C", "H"]
+        } else {
+            &[]
+        };
+
+        let order = first
+            .iter()
+            .copied()
+            .filter(|symbol| counts.contains_key(symbol))
+            .chain(
+                counts
+                    .keys()
+                    .copied()
+                    .filter(|symbol| !first.contains(symbol)),
+            );
+
+        order
+            .map(|symbol| match counts[symbol] {
+                1 => symbol.to_owned(),
+                count => format!("{}{}", symbol, count),
+            })
+            .collect()
+    }
+
+    /// The sum of the atomic masses, in daltons
+    pub fn molecular_weight(&self) -> f32 {
+        self.atoms.iter().map(|atom| atom.element.mass()).sum()
+    }
+
+    /// The mean position of the atoms weighted by their masses
+    pub fn center_of_mass(&self) -> [f32; 3] {
+        let weight = self.molecular_weight();
+        if weight <= 0.0 {
+            return self.center();
+        }
+
+        self.atoms
+            .iter()
+            .fold([0.0, 0.0, 0.0], |sum, atom| {
+                sum.add(&atom.position.scale(atom.element.mass()))
+            })
+            .scale(1.0 / weight)
+    }
+
+    /// The moment of inertia tensor about the center of mass, in Da·Å²
+    pub fn inertia_tensor(&self) -> [[f32; 3]; 3] {
+        let center = self.center_of_mass();
+        let mut tensor = [[0.0; 3]; 3];
+
+        for atom in &self.atoms {
+            let mass = atom.element.mass();
+            let r = atom.position.sub(&center);
+            let r2 = r.mag2();
+
+            for (i, row) in tensor.iter_mut().enumerate() {
+                for (j, value) in row.iter_mut().enumerate() {
+                    let diagonal = if i == j { r2 } else { 0.0 };
+                    *value += mass * (diagonal - r[i] * r[j]);
+                }
+            }
+        }
+
+        tensor
+    }
+
+    /// The principal moments of inertia, smallest first, and the unit axis of each
+    pub fn principal_axes(&self) -> ([f32; 3], [[f32; 3]; 3]) {
+        #![allow(clippy::cast_possible_truncation)]
+
+        let tensor = self.inertia_tensor().map(|row| row.map(f64::from));
+        let (moments, axes) = symmetric_eigen(&tensor);
+
+        (
+            moments.map(|moment| moment as f32),
+            axes.map(|axis| axis.map(|value| value as f32)),
+        )
+    }
+
+    /// A sphere enclosing the centers of every atom, by Ritter's algorithm. It may be a
+    /// few percent larger than the smallest such sphere.
+    pub fn bounding_sphere(&self) -> ([f32; 3], f32) {
+        let positions: Vec<[f32; 3]> = self.atoms.iter().map(|atom| atom.position).collect();
+
+        let first = match positions.first() {
+            Some(first) => *first,
+            None => return ([0.0, 0.0, 0.0], 0.0),
+        };
+
+        let farthest = |from: &[f32; 3]| {
+            positions
+                .iter()
+                .copied()
+                .max_by(|a, b| a.sub(from).mag2().total_cmp(&b.sub(from).mag2()))
+                .unwrap_or(*from)
+        };
+
+        let a = farthest(&first);
+        let b = farthest(&a);
+        let mut center = a.add(&b).scale(0.5);
+        let mut radius = b.sub(&a).mag() / 2.0;
+
+        for position in &positions {
+            let distance = position.sub(&center).mag();
+            if distance > radius {
+                // Grow the sphere just enough to reach this atom
+                radius = (radius + distance) / 2.0;
+                let towards = position.sub(&center).scale((distance - radius) / distance);
+                center = center.add(&towards);
+            }
+        }
+
+        (center, radius)
+    }
+}
@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use webgl_matrix::Vector;
+
+use super::perception::cell;
+use super::{Element, Molecule};
+
+/// Words which end a list of values, such as the names after `name`. Selections
+/// without values, such as `hydrogen`, are left out so they can be values themselves,
+/// as in `element hydrogen`.
+const KEYWORDS: &[&str] = &[
+    "and", "or", "not", "within", "of", "element", "name", "resname", "resid", "chain", "type",
+    "index", "serial",
+];
+
+/// A problem with a query, pointing at the token where it was found
+#[derive(Debug)]
+pub struct QueryError {
+    /// Column of the query, starting at 1
+    pub column: usize,
+    /// The failing token, empty when the query ended too soon
+    pub token: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: {}", self.column, self.reason)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A word or parenthesis of a query, and where it starts
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+/// Split a query on whitespace, with each parenthesis a token of its own. Columns
+/// count characters rather than bytes, so they hold for names which are not ASCII.
+fn tokenize(query: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (column, (i, c)) in (1..).zip(
+        query
+            .char_indices()
+            .chain(std::iter::once((query.len(), ' '))),
+    ) {
+        let parenthesis = c == '(' || c == ')';
+
+        if let Some((begin, begin_column)) = start {
+            if c.is_whitespace() || parenthesis {
+                tokens.push(Token {
+                    text: &query[begin..i],
+                    column: begin_column,
+                });
+                start = None;
+            }
+        }
+
+        if parenthesis {
+            tokens.push(Token {
+                text: &query[i..=i],
+                column,
+            });
+        } else if start.is_none() && !c.is_whitespace() {
+            start = Some((i, column));
+        }
+    }
+
+    tokens
+}
+
+/// A query for atoms, such as `element N and within 3.5 of resname LIG`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    All,
+    None,
+    Hydrogen,
+    /// Atoms of `HETATM` records
+    Hetero,
+    /// Atoms of aromatic rings
+    Aromatic,
+    Element(Vec<Element>),
+    Name(Vec<String>),
+    ResidueName(Vec<String>),
+    Chain(Vec<String>),
+    AtomType(Vec<String>),
+    /// Position in `Molecule::atoms`, starting at 0
+    Index(Vec<RangeInclusive<i64>>),
+    ResidueNumber(Vec<RangeInclusive<i64>>),
+    /// Serial number given by the file
+    Serial(Vec<RangeInclusive<i64>>),
+    /// Atoms closer than a distance, in Angstroms, to any atom of the inner query,
+    /// including those atoms themselves
+    Within(f32, Box<Query>),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+/// Recursive descent through the tokens of a query. From loosest to tightest, `or`
+/// binds looser than `and`, which binds looser than `not` and `within`.
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    position: usize,
+    /// Column where the query ends, for errors about missing tokens
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    /// Consume the next token when it is the keyword `word`
+    fn accept(&mut self, word: &str) -> bool {
+        let found = self
+            .peek()
+            .is_some_and(|token| token.text.eq_ignore_ascii_case(word));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    /// An error at `token`, or at the end of the query when it is `None`
+    fn error(&self, token: Option<Token<'_>>, reason: impl Into<String>) -> QueryError {
+        QueryError {
+            column: token.map_or(self.end + 1, |token| token.column),
+            token: token.map_or_else(String::new, |token| token.text.to_owned()),
+            reason: reason.into(),
+        }
+    }
+
+    /// Describe what was found instead of what was expected
+    fn found(token: Option<Token<'_>>) -> String {
+        token.map_or_else(
+            || "the end of the query".to_owned(),
+            |token| format!("'{}'", token.text),
+        )
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.and()?;
+        while self.accept("or") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.unary()?;
+        while self.accept("and") {
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query, QueryError> {
+        if self.accept("not") {
+            return Ok(Query::Not(Box::new(self.unary()?)));
+        }
+
+        if self.accept("within") {
+            let token = self.next();
+            let distance = token
+                .and_then(|token| token.text.parse::<f32>().ok())
+                .filter(|distance| distance.is_finite() && *distance >= 0.0)
+                .ok_or_else(|| {
+                    self.error(
+                        token,
+                        format!("Expected a distance, found {}", Self::found(token)),
+                    )
+                })?;
+
+            if !self.accept("of") {
+                let token = self.peek();
+                return Err(self.error(
+                    token,
+                    format!("Expected 'of', found {}", Self::found(token)),
+                ));
+            }
+
+            return Ok(Query::Within(distance, Box::new(self.unary()?)));
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Query, QueryError> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => {
+                return Err(self.error(None, "Expected a selection, found the end of the query"))
+            }
+        };
+
+        let query = match token.text.to_ascii_lowercase().as_str() {
+            "(" => {
+                let query = self.or()?;
+                let close = self.next();
+                if close.map(|close| close.text) != Some(")") {
+                    let reason = format!(
+                        "Expected ')' to close the '(' at column {}, found {}",
+                        token.column,
+                        Self::found(close)
+                    );
+                    return Err(self.error(close, reason));
+                }
+                query
+            }
+            "all" => Query::All,
+            "none" => Query::None,
+            "hydrogen" => Query::Hydrogen,
+            "hetero" => Query::Hetero,
+            "aromatic" => Query::Aromatic,
+            "element" => Query::Element(self.elements()?),
+            "name" => Query::Name(self.words("an atom name")?),
+            "resname" => Query::ResidueName(self.words("a residue name")?),
+            "chain" => Query::Chain(self.words("a chain")?),
+            "type" => Query::AtomType(self.words("an atom type")?),
+            "index" => Query::Index(self.ranges()?),
+            "resid" => Query::ResidueNumber(self.ranges()?),
+            "serial" => Query::Serial(self.ranges()?),
+            _ => {
+                let reason = format!("Unexpected '{}'", token.text);
+                return Err(self.error(Some(token), reason));
+            }
+        };
+
+        Ok(query)
+    }
+
+    /// The tokens up to the next keyword or parenthesis, of which there must be one
+    fn values(&mut self, what: &str) -> Result<Vec<Token<'a>>, QueryError> {
+        let mut values = Vec::new();
+
+        while let Some(token) = self.peek() {
+            let keyword = KEYWORDS
+                .iter()
+                .any(|keyword| token.text.eq_ignore_ascii_case(keyword));
+            if keyword || token.text == "(" || token.text == ")" {
+                break;
+            }
+            values.push(token);
+            self.position += 1;
+        }
+
+        if values.is_empty() {
+            let token = self.peek();
+            return Err(self.error(
+                token,
+                format!("Expected {}, found {}", what, Self::found(token)),
+            ));
+        }
+
+        Ok(values)
+    }
+
+    /// Element symbols or names, or atomic numbers
+    fn elements(&mut self) -> Result<Vec<Element>, QueryError> {
+        self.values("an element")?
+            .into_iter()
+            .map(|token| match Element::from(token.text) {
+                Element::Other => {
+                    let reason = format!("Unknown element '{}'", token.text);
+                    Err(self.error(Some(token), reason))
+                }
+                element => Ok(element),
+            })
+            .collect()
+    }
+
+    fn words(&mut self, what: &str) -> Result<Vec<String>, QueryError> {
+        Ok(self
+            .values(what)?
+            .into_iter()
+            .map(|token| token.text.to_owned())
+            .collect())
+    }
+
+    /// Numbers, or ranges of them such as `1-20`, which include both ends
+    fn ranges(&mut self) -> Result<Vec<RangeInclusive<i64>>, QueryError> {
+        let what = "a number or a range such as 1-20";
+
+        self.values(what)?
+            .into_iter()
+            .map(|token| {
+                let text = token.text;
+
+                // A leading minus sign is part of the first number
+                let range = match text.get(1..).and_then(|rest| rest.find('-')) {
+                    Some(dash) => text[..=dash]
+                        .parse()
+                        .and_then(|first| Ok(first..=text[dash + 2..].parse()?)),
+                    None => text.parse().map(|number| number..=number),
+                };
+
+                range.map_err(|_| {
+                    self.error(Some(token), format!("Expected {}, found '{}'", what, text))
+                })
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(query),
+            position: 0,
+            end: query.trim_end().chars().count(),
+        };
+
+        let parsed = parser.or()?;
+
+        match parser.peek() {
+            None => Ok(parsed),
+            Some(token) => Err(parser.error(
+                Some(token),
+                format!("Expected 'and' or 'or', found '{}'", token.text),
+            )),
+        }
+    }
+}
+
+impl Query {
+    /// Whether each atom of the molecule matches
+    fn matches(&self, molecule: &Molecule) -> Vec<bool> {
+        #![allow(clippy::cast_possible_wrap)]
+
+        let atoms = &molecule.atoms;
+        let each = |test: &dyn Fn(usize) -> bool| (0..atoms.len()).map(test).collect();
+        let within = |ranges: &[RangeInclusive<i64>], value: i64| {
+            ranges.iter().any(|range| range.contains(&value))
+        };
+
+        match self {
+            Self::All => vec![true; atoms.len()],
+            Self::None => vec![false; atoms.len()],
+            Self::Hydrogen => each(&|i| atoms[i].element == Element::Hydrogen),
+            Self::Hetero => each(&|i| atoms[i].hetero),
+            Self::Aromatic => {
+                let mut matches = vec![false; atoms.len()];
+                for ring in molecule.rings.iter().filter(|ring| ring.aromatic) {
+                    for &atom in &ring.atoms {
+                        matches[atom] = true;
+                    }
+                }
+                matches
+            }
+            Self::Element(elements) => each(&|i| elements.contains(&atoms[i].element)),
+            Self::Name(names) => each(&|i| names.contains(&atoms[i].name)),
+            Self::ResidueName(names) => each(&|i| names.contains(&atoms[i].residue_name)),
+            Self::Chain(chains) => each(&|i| chains.contains(&atoms[i].chain)),
+            Self::AtomType(types) => each(&|i| types.contains(&atoms[i].atom_type)),
+            Self::Index(ranges) => each(&|i| within(ranges, i as i64)),
+            Self::ResidueNumber(ranges) => {
+                each(&|i| within(ranges, i64::from(atoms[i].residue_number)))
+            }
+            Self::Serial(ranges) => each(&|i| within(ranges, i64::from(atoms[i].serial))),
+            Self::Within(distance, inner) => {
+                Self::near(molecule, *distance, &inner.matches(molecule))
+            }
+            Self::Not(inner) => inner.matches(molecule).into_iter().map(|m| !m).collect(),
+            Self::And(first, second) => first
+                .matches(molecule)
+                .into_iter()
+                .zip(second.matches(molecule))
+                .map(|(first, second)| first && second)
+                .collect(),
+            Self::Or(first, second) => first
+                .matches(molecule)
+                .into_iter()
+                .zip(second.matches(molecule))
+                .map(|(first, second)| first || second)
+                .collect(),
+        }
+    }
+
+    /// The atoms no further than `distance` from any of the `centers`. The centers are
+    /// sorted into cells as wide as the distance, so only the 27 cells around each atom
+    /// need to be searched.
+    fn near(molecule: &Molecule, distance: f32, centers: &[bool]) -> Vec<bool> {
+        let atoms = &molecule.atoms;
+        let size = distance.max(0.1);
+
+        let mut cells = HashMap::<(i32, i32, i32), Vec<usize>>::new();
+        for (i, atom) in atoms.iter().enumerate().filter(|&(i, _)| centers[i]) {
+            cells.entry(cell(&atom.position, size)).or_default().push(i);
+        }
+
+        atoms
+            .iter()
+            .map(|atom| {
+                let (x, y, z) = cell(&atom.position, size);
+                (-1..=1).any(|dx| {
+                    (-1..=1).any(|dy| {
+                        (-1..=1).any(|dz| {
+                            cells.get(&(x + dx, y + dy, z + dz)).is_some_and(|members| {
+                                members.iter().any(|&j| {
+                                    atoms[j].position.sub(&atom.position).mag() <= distance
+                                })
+                            })
+                        })
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+impl Molecule {
+    /// The indices of the atoms matching a query, in ascending order
+    pub fn select(&self, query: &Query) -> Vec<usize> {
+        query
+            .matches(self)
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, matches)| matches)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str) -> Query {
+        query.parse().unwrap()
+    }
+
+    /// The column and token of the error in a query
+    fn error(query: &str) -> (usize, String) {
+        let error = query.parse::<Query>().unwrap_err();
+        (error.column, error.token)
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(
+            parse("element hydrogen C 8"),
+            Query::Element(vec![Element::Hydrogen, Element::Carbon, Element::Oxygen])
+        );
+        assert_eq!(
+            parse("NAME CA CB"),
+            Query::Name(vec!["CA".to_owned(), "CB".to_owned()])
+        );
+        assert_eq!(
+            parse("resid 1-20 -3 -5--2"),
+            Query::ResidueNumber(vec![1..=20, -3..=-3, -5..=-2])
+        );
+    }
+
+    #[test]
+    fn parses_operators_by_precedence() {
+        let boxed = Box::new;
+
+        assert_eq!(
+            parse("element H and hydrogen or not aromatic"),
+            Query::Or(
+                boxed(Query::And(
+                    boxed(Query::Element(vec![Element::Hydrogen])),
+                    boxed(Query::Hydrogen)
+                )),
+                boxed(Query::Not(boxed(Query::Aromatic)))
+            )
+        );
+        assert_eq!(
+            parse("hetero and (all or none)"),
+            Query::And(
+                boxed(Query::Hetero),
+                boxed(Query::Or(boxed(Query::All), boxed(Query::None)))
+            )
+        );
+        assert_eq!(
+            parse("within 3.5 of chain A and hydrogen"),
+            Query::And(
+                boxed(Query::Within(
+                    3.5,
+                    boxed(Query::Chain(vec!["A".to_owned()]))
+                )),
+                boxed(Query::Hydrogen)
+            )
+        );
+    }
+
+    #[test]
+    fn reports_the_column_of_errors() {
+        assert_eq!(error("name Cα Cβ )"), (12, ")".to_owned()));
+        assert_eq!(error("name Cα and"), (12, String::new()));
+        assert_eq!(error("element Xx"), (9, "Xx".to_owned()));
+        assert_eq!(error("resid 1-a"), (7, "1-a".to_owned()));
+        assert_eq!(error("within -1 of all"), (8, "-1".to_owned()));
+        assert_eq!(error("within 2 all"), (10, "all".to_owned()));
+        assert_eq!(error("(all or none"), (13, String::new()));
+        assert_eq!(error("all bogus"), (5, "bogus".to_owned()));
+    }
+}
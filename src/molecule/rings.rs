@@ -0,0 +1,457 @@
+use std::collections::{HashMap, HashSet};
+
+use webgl_matrix::Vector;
+
+use super::{Atom, Bond, BondOrder};
+
+/// Rings with more atoms than this, such as the backbone cycles of macrocycles, are
+/// not searched for
+const MAX_RING_SIZE: usize = 24;
+
+/// Atoms of an aromatic ring lie within this distance of the plane of the ring
+const PLANARITY_TOLERANCE: f32 = 0.1;
+
+/// Bonds of an aromatic ring are shorter than this fraction of the sum of the covalent
+/// radii of their atoms, which excludes the single bonds of quinones and cyclopentadiene
+const AROMATIC_BOND_RATIO: f32 = 0.965;
+
+pub struct Ring {
+    /// Indices into `Molecule::atoms`, in order around the ring
+    pub atoms: Vec<usize>,
+    pub aromatic: bool,
+}
+
+impl Ring {
+    /// The pairs of atoms bonded around the ring
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let count = self.atoms.len();
+        (0..count).map(move |i| (self.atoms[i], self.atoms[(i + 1) % count]))
+    }
+
+    pub fn center(&self, atoms: &[Atom]) -> [f32; 3] {
+        #![allow(clippy::cast_precision_loss)]
+
+        self.atoms
+            .iter()
+            .fold([0.0, 0.0, 0.0], |sum, &atom| sum.add(&atoms[atom].position))
+            .scale(1.0 / self.atoms.len() as f32)
+    }
+
+    /// The unit normal of the plane which best fits the ring, by Newell's method
+    pub fn normal(&self, atoms: &[Atom]) -> [f32; 3] {
+        let mut normal = [0.0, 0.0, 0.0];
+
+        for (i, j) in self.edges() {
+            let a = atoms[i].position;
+            let b = atoms[j].position;
+            normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+            normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+            normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+        }
+
+        let length = normal.mag();
+        if length > 0.0 {
+            normal.scale(1.0 / length)
+        } else {
+            normal
+        }
+    }
+}
+
+/// The atoms of the smallest ring through the bond between `first` and `second`,
+/// found by a breadth first search from `first` which avoids the bond itself
+fn smallest_ring(neighbors: &[Vec<usize>], first: usize, second: usize) -> Option<Vec<usize>> {
+    let mut parent = HashMap::<usize, usize>::new();
+    let mut frontier = vec![first];
+    parent.insert(first, first);
+
+    for _ in 1..MAX_RING_SIZE {
+        let mut next = Vec::<usize>::new();
+
+        for &atom in &frontier {
+            for &neighbor in &neighbors[atom] {
+                if (atom == first && neighbor == second) || parent.contains_key(&neighbor) {
+                    continue;
+                }
+
+                parent.insert(neighbor, atom);
+                if neighbor == second {
+                    let mut ring = vec![second];
+                    let mut current = atom;
+                    while current != first {
+                        ring.push(current);
+                        current = parent[&current];
+                    }
+                    ring.push(first);
+                    return Some(ring);
+                }
+                next.push(neighbor);
+            }
+        }
+
+        frontier = next;
+    }
+
+    None
+}
+
+/// The bonds which are not part of any ring, such as those of chains and the links
+/// between ring systems, found with Tarjan's depth first search. The search keeps its
+/// own stack, as chains such as protein backbones are far too long to recurse along.
+fn bridges(neighbors: &[Vec<usize>]) -> HashSet<(usize, usize)> {
+    const UNVISITED: usize = usize::MAX;
+
+    let mut order = vec![UNVISITED; neighbors.len()];
+    let mut low = vec![0; neighbors.len()];
+    let mut visited = 0;
+    let mut bridges = HashSet::new();
+
+    for root in 0..neighbors.len() {
+        if order[root] != UNVISITED {
+            continue;
+        }
+
+        order[root] = visited;
+        low[root] = visited;
+        visited += 1;
+
+        // Each atom of the current path, the atom it was reached from and the index of
+        // the next neighbor to visit
+        let mut stack = vec![(root, UNVISITED, 0)];
+
+        while let Some(top) = stack.last_mut() {
+            let (atom, parent, next) = *top;
+
+            if let Some(&neighbor) = neighbors[atom].get(next) {
+                top.2 += 1;
+
+                if neighbor == parent {
+                    continue;
+                }
+
+                if order[neighbor] == UNVISITED {
+                    order[neighbor] = visited;
+                    low[neighbor] = visited;
+                    visited += 1;
+                    stack.push((neighbor, atom, 0));
+                } else {
+                    low[atom] = low[atom].min(order[neighbor]);
+                }
+            } else {
+                stack.pop();
+
+                if parent != UNVISITED {
+                    low[parent] = low[parent].min(low[atom]);
+
+                    // Nothing below the atom reaches back above its parent
+                    if low[atom] > order[parent] {
+                        bridges.insert((atom.min(parent), atom.max(parent)));
+                    }
+                }
+            }
+        }
+    }
+
+    bridges
+}
+
+fn find_root(roots: &mut [usize], atom: usize) -> usize {
+    let mut root = atom;
+    while roots[root] != root {
+        roots[root] = roots[roots[root]];
+        root = roots[root];
+    }
+    root
+}
+
+/// The number of independent rings, which is the number of bonds less the number of
+/// atoms plus the number of connected fragments
+fn ring_count(atom_count: usize, pairs: &HashSet<(usize, usize)>) -> usize {
+    let mut roots: Vec<usize> = (0..atom_count).collect();
+    let mut fragments = atom_count;
+
+    for &(i, j) in pairs {
+        let (a, b) = (find_root(&mut roots, i), find_root(&mut roots, j));
+        if a != b {
+            roots[a] = b;
+            fragments -= 1;
+        }
+    }
+
+    (pairs.len() + fragments).saturating_sub(atom_count)
+}
+
+/// Find the smallest set of smallest rings. The smallest ring through every bond is a
+/// candidate, and the candidates are taken from the smallest up as long as they are
+/// independent of those already taken, treating each ring as a set of bonds. Bridges
+/// are left out first, so chains cost nothing to search.
+fn smallest_set_of_smallest_rings(atom_count: usize, bonds: &[Bond]) -> Vec<Vec<usize>> {
+    let pairs: HashSet<(usize, usize)> = bonds
+        .iter()
+        .map(|bond| {
+            let [i, j] = bond.atoms;
+            (i.min(j), i.max(j))
+        })
+        .filter(|(i, j)| i != j)
+        .collect();
+
+    let needed = ring_count(atom_count, &pairs);
+    if needed == 0 {
+        return Vec::new();
+    }
+
+    let mut neighbors = vec![Vec::<usize>::new(); atom_count];
+    for &(i, j) in &pairs {
+        neighbors[i].push(j);
+        neighbors[j].push(i);
+    }
+
+    let bridges = bridges(&neighbors);
+    let mut sorted_pairs: Vec<_> = pairs.difference(&bridges).copied().collect();
+    sorted_pairs.sort_unstable();
+
+    let mut neighbors = vec![Vec::<usize>::new(); atom_count];
+    for &(i, j) in &sorted_pairs {
+        neighbors[i].push(j);
+        neighbors[j].push(i);
+    }
+
+    let mut seen = HashSet::<Vec<usize>>::new();
+    let mut candidates = Vec::<Vec<usize>>::new();
+
+    for (i, j) in sorted_pairs {
+        if let Some(ring) = smallest_ring(&neighbors, i, j) {
+            let mut key = ring.clone();
+            key.sort_unstable();
+            if seen.insert(key) {
+                candidates.push(ring);
+            }
+        }
+    }
+    candidates.sort_by_key(Vec::len);
+
+    // Number the bonds which are part of a candidate, for the bit sets below
+    let mut edge_index = HashMap::<(usize, usize), usize>::new();
+    for ring in &candidates {
+        for k in 0..ring.len() {
+            let (i, j) = (ring[k], ring[(k + 1) % ring.len()]);
+            let next = edge_index.len();
+            edge_index.entry((i.min(j), i.max(j))).or_insert(next);
+        }
+    }
+    let words = edge_index.len().div_ceil(64);
+
+    let mut basis = Vec::<(usize, Vec<u64>)>::new();
+    let mut rings = Vec::<Vec<usize>>::new();
+
+    for ring in candidates {
+        let mut bits = vec![0_u64; words];
+        for k in 0..ring.len() {
+            let (i, j) = (ring[k], ring[(k + 1) % ring.len()]);
+            let edge = edge_index[&(i.min(j), i.max(j))];
+            bits[edge / 64] ^= 1 << (edge % 64);
+        }
+
+        // Gaussian elimination over GF(2), where adding rings is a symmetric difference
+        for (pivot, vector) in &basis {
+            if bits[pivot / 64] & (1 << (pivot % 64)) != 0 {
+                for (word, other) in bits.iter_mut().zip(vector) {
+                    *word ^= other;
+                }
+            }
+        }
+
+        let pivot = bits
+            .iter()
+            .enumerate()
+            .find(|(_, word)| **word != 0)
+            .map(|(index, word)| index * 64 + word.trailing_zeros() as usize);
+
+        if let Some(pivot) = pivot {
+            basis.push((pivot, bits));
+            rings.push(ring);
+
+            if rings.len() == needed {
+                break;
+            }
+        }
+    }
+
+    rings
+}
+
+/// A ring is aromatic when the file says all of its bonds are, or when it is planar
+/// with every bond shortened by conjugation
+fn is_aromatic(atoms: &[Atom], orders: &HashMap<(usize, usize), BondOrder>, ring: &Ring) -> bool {
+    let order = |i: usize, j: usize| orders.get(&(i.min(j), i.max(j))).copied();
+
+    if ring
+        .edges()
+        .all(|(i, j)| order(i, j) == Some(BondOrder::Aromatic))
+    {
+        return true;
+    }
+
+    let center = ring.center(atoms);
+    let normal = ring.normal(atoms);
+    let planar = ring.atoms.iter().all(|&atom| {
+        let offset = atoms[atom].position.sub(&center);
+        offset.dot(&normal).abs() < PLANARITY_TOLERANCE
+    });
+
+    let conjugated = ring.edges().all(|(i, j)| {
+        let length = atoms[i].position.sub(&atoms[j].position).mag();
+        let single = atoms[i].element.covalent_radius() + atoms[j].element.covalent_radius();
+        length < single * AROMATIC_BOND_RATIO
+    });
+
+    (5..=7).contains(&ring.atoms.len()) && planar && conjugated
+}
+
+/// Find the smallest set of smallest rings, marking those which are aromatic
+pub fn find_rings(atoms: &[Atom], bonds: &[Bond]) -> Vec<Ring> {
+    let orders: HashMap<(usize, usize), BondOrder> = bonds
+        .iter()
+        .map(|bond| {
+            let [i, j] = bond.atoms;
+            ((i.min(j), i.max(j)), bond.order)
+        })
+        .collect();
+
+    smallest_set_of_smallest_rings(atoms.len(), bonds)
+        .into_iter()
+        .map(|atoms_of_ring| {
+            let mut ring = Ring {
+                atoms: atoms_of_ring,
+                aromatic: false,
+            };
+            ring.aromatic = is_aromatic(atoms, &orders, &ring);
+            ring
+        })
+        .collect()
+}
+
+/// Give the bonds of aromatic rings the aromatic order
+pub fn mark_aromatic(rings: &[Ring], bonds: &mut [Bond]) {
+    let aromatic: HashSet<(usize, usize)> = rings
+        .iter()
+        .filter(|ring| ring.aromatic)
+        .flat_map(Ring::edges)
+        .map(|(i, j)| (i.min(j), i.max(j)))
+        .collect();
+
+    for bond in bonds {
+        let [i, j] = bond.atoms;
+        if aromatic.contains(&(i.min(j), i.max(j))) {
+            bond.order = BondOrder::Aromatic;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::molecule::Element;
+
+    /// Bonds of unknown order between consecutive atoms of each cycle, and between
+    /// the given pairs
+    fn bonds(cycles: &[&[usize]], links: &[(usize, usize)]) -> Vec<Bond> {
+        let mut bonds = Vec::new();
+        for cycle in cycles {
+            for k in 0..cycle.len() {
+                let (i, j) = (cycle[k], cycle[(k + 1) % cycle.len()]);
+                bonds.push(Bond::new(i, j, BondOrder::Unknown));
+            }
+        }
+        for &(i, j) in links {
+            bonds.push(Bond::new(i, j, BondOrder::Unknown));
+        }
+        bonds
+    }
+
+    #[test]
+    fn finds_the_bridges_between_rings() {
+        // Biphenyl, with a methyl group on each ring
+        let bonds = bonds(
+            &[&[0, 1, 2, 3, 4, 5], &[6, 7, 8, 9, 10, 11]],
+            &[(0, 6), (3, 12), (9, 13)],
+        );
+
+        let mut neighbors = vec![Vec::new(); 14];
+        for bond in &bonds {
+            let [i, j] = bond.atoms;
+            neighbors[i].push(j);
+            neighbors[j].push(i);
+        }
+
+        let expected: HashSet<_> = [(0, 6), (3, 12), (9, 13)].iter().copied().collect();
+        assert_eq!(bridges(&neighbors), expected);
+
+        let rings = smallest_set_of_smallest_rings(14, &bonds);
+        assert_eq!(rings.len(), 2);
+        assert!(rings.iter().all(|ring| ring.len() == 6));
+    }
+
+    /// Carbon atoms at the corners of a regular hexagon with sides of `length`
+    fn hexagon(length: f32) -> Vec<Atom> {
+        #![allow(clippy::cast_precision_loss)]
+
+        (0..6)
+            .map(|k| {
+                let angle = k as f32 * std::f32::consts::PI / 3.0;
+                let position = [length * angle.cos(), length * angle.sin(), 0.0];
+                Atom::new(Element::Carbon, position)
+            })
+            .collect()
+    }
+
+    fn sorted(mut rings: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+        for ring in &mut rings {
+            ring.sort_unstable();
+        }
+        rings.sort();
+        rings
+    }
+
+    #[test]
+    fn finds_the_ring_of_benzene() {
+        let bonds = bonds(&[&[0, 1, 2, 3, 4, 5]], &[]);
+
+        let rings = find_rings(&hexagon(1.39), &bonds);
+        assert_eq!(rings.len(), 1);
+        assert_eq!(sorted(vec![rings[0].atoms.clone()]), [[0, 1, 2, 3, 4, 5]]);
+        assert!(rings[0].aromatic);
+
+        let rings = find_rings(&hexagon(1.54), &bonds);
+        assert_eq!(rings.len(), 1);
+        assert!(!rings[0].aromatic);
+    }
+
+    #[test]
+    fn finds_the_two_rings_of_naphthalene() {
+        // The fused bond is between atoms 0 and 5
+        let bonds = bonds(
+            &[&[0, 1, 2, 3, 4, 5]],
+            &[(5, 6), (6, 7), (7, 8), (8, 9), (9, 0)],
+        );
+
+        let rings = sorted(smallest_set_of_smallest_rings(10, &bonds));
+        assert_eq!(rings, [vec![0, 1, 2, 3, 4, 5], vec![0, 5, 6, 7, 8, 9]]);
+    }
+
+    #[test]
+    fn finds_the_five_faces_of_cubane() {
+        // Two squares, with the corners of one above those of the other
+        let bonds = bonds(
+            &[&[0, 1, 2, 3], &[4, 5, 6, 7]],
+            &[(0, 4), (1, 5), (2, 6), (3, 7)],
+        );
+
+        let rings = smallest_set_of_smallest_rings(8, &bonds);
+        assert_eq!(rings.len(), 5);
+        assert!(rings.iter().all(|ring| ring.len() == 4));
+
+        let faces: HashSet<Vec<usize>> = sorted(rings).into_iter().collect();
+        assert_eq!(faces.len(), 5);
+    }
+}
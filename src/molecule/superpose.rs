@@ -0,0 +1,285 @@
+use webgl_matrix::Vector;
+
+use super::properties::symmetric_eigen;
+use super::{AtomProperty, Molecule};
+
+/// Names of the extended XYZ properties which are vectors, and turn with the atoms.
+/// Other properties with three columns, such as colors, are left as they are.
+const VECTOR_PROPERTIES: &[&str] = &[
+    "forces",
+    "force",
+    "velocities",
+    "velo",
+    "vel",
+    "momenta",
+    "dipole",
+    "dipoles",
+];
+
+/// A rotation followed by a translation which moves one molecule onto another
+#[derive(Debug, Clone, Copy)]
+pub struct Superposition {
+    /// Rotation matrix, row by row
+    pub rotation: [[f32; 3]; 3],
+    pub translation: [f32; 3],
+    /// Root mean square deviation of the mapped atoms once superposed, in Angstroms
+    pub rmsd: f32,
+}
+
+impl Superposition {
+    pub fn rotate(&self, vector: &[f32; 3]) -> [f32; 3] {
+        self.rotation.map(|row| row.dot(vector))
+    }
+
+    pub fn apply(&self, position: &[f32; 3]) -> [f32; 3] {
+        self.rotate(position).add(&self.translation)
+    }
+}
+
+/// The mean of the mapped positions, in double precision
+fn centroid(positions: &[[f64; 3]]) -> [f64; 3] {
+    #![allow(clippy::cast_precision_loss)]
+
+    let mut sum = [0.0; 3];
+    for position in positions {
+        for (total, value) in sum.iter_mut().zip(position) {
+            *total += value;
+        }
+    }
+
+    sum.map(|total| total / positions.len() as f64)
+}
+
+/// The rotation which best superposes `moving` onto `fixed`, both already centred, and
+/// the RMSD it leaves. This solves the same least squares problem as the Kabsch
+/// algorithm, but through Horn's unit quaternion, which needs only the eigenvectors of
+/// a symmetric matrix and can never produce a reflection.
+fn best_rotation(moving: &[[f64; 3]], fixed: &[[f64; 3]]) -> ([[f64; 3]; 3], f64) {
+    #![allow(clippy::cast_precision_loss, clippy::many_single_char_names)]
+
+    let mut s = [[0.0; 3]; 3];
+    let mut squares = 0.0;
+    for (p, q) in moving.iter().zip(fixed) {
+        for i in 0..3 {
+            for j in 0..3 {
+                s[i][j] += p[i] * q[j];
+            }
+            squares += p[i] * p[i] + q[i] * q[i];
+        }
+    }
+
+    let [[xx, xy, xz], [yx, yy, yz], [zx, zy, zz]] = s;
+    let n = [
+        [xx + yy + zz, yz - zy, zx - xz, xy - yx],
+        [yz - zy, xx - yy - zz, xy + yx, zx + xz],
+        [zx - xz, xy + yx, yy - xx - zz, yz + zy],
+        [xy - yx, zx + xz, yz + zy, zz - xx - yy],
+    ];
+
+    let (values, vectors) = symmetric_eigen(&n);
+    let [w, x, y, z] = vectors[3];
+
+    let rotation = [
+        [
+            w * w + x * x - y * y - z * z,
+            2.0 * (x * y - w * z),
+            2.0 * (x * z + w * y),
+        ],
+        [
+            2.0 * (x * y + w * z),
+            w * w - x * x + y * y - z * z,
+            2.0 * (y * z - w * x),
+        ],
+        [
+            2.0 * (x * z - w * y),
+            2.0 * (y * z + w * x),
+            w * w - x * x - y * y + z * z,
+        ],
+    ];
+
+    let deviation = (squares - 2.0 * values[3]).max(0.0) / moving.len() as f64;
+    (rotation, deviation.sqrt())
+}
+
+/// Rotate the properties known to be vectors, such as forces and velocities
+fn rotate_vectors(properties: &mut [AtomProperty], superposition: &Superposition) {
+    let vector = |property: &&mut AtomProperty| {
+        property.columns == 3
+            && VECTOR_PROPERTIES
+                .iter()
+                .any(|name| property.name.eq_ignore_ascii_case(name))
+    };
+
+    for property in properties.iter_mut().filter(vector) {
+        for vector in property.values.chunks_exact_mut(3) {
+            let rotated = superposition.rotate(&[vector[0], vector[1], vector[2]]);
+            vector.copy_from_slice(&rotated);
+        }
+    }
+}
+
+impl Molecule {
+    /// Find the superposition of this molecule onto `reference` which minimises the
+    /// RMSD between pairs of atoms. Each pair in `mapping` holds the index of an atom
+    /// of this molecule then that of its counterpart in `reference`. Without a mapping
+    /// the atoms are paired by index, which needs both molecules to have as many atoms.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the mapping is empty or names an atom which does not exist
+    pub fn superposition(
+        &self,
+        reference: &Self,
+        mapping: Option<&[(usize, usize)]>,
+    ) -> Result<Superposition, String> {
+        #![allow(clippy::cast_possible_truncation)]
+
+        let by_index: Vec<(usize, usize)>;
+        let mapping = match mapping {
+            Some(mapping) => mapping,
+            None if self.atoms.len() == reference.atoms.len() => {
+                by_index = (0..self.atoms.len()).map(|i| (i, i)).collect();
+                &by_index
+            }
+            None => {
+                return Err(format!(
+                    "Cannot pair {} atoms with {} by index",
+                    self.atoms.len(),
+                    reference.atoms.len()
+                ))
+            }
+        };
+
+        if mapping.is_empty() {
+            return Err("No atoms to superpose".to_owned());
+        }
+
+        let mut moving = Vec::<[f64; 3]>::with_capacity(mapping.len());
+        let mut fixed = Vec::<[f64; 3]>::with_capacity(mapping.len());
+
+        for &(i, j) in mapping {
+            let (first, second) = match (self.atoms.get(i), reference.atoms.get(j)) {
+                (Some(first), Some(second)) => (first, second),
+                (None, _) => return Err(format!("No atom {} to superpose", i)),
+                (_, None) => return Err(format!("No atom {} in the reference", j)),
+            };
+
+            moving.push(first.position.map(f64::from));
+            fixed.push(second.position.map(f64::from));
+        }
+
+        let (moving_center, fixed_center) = (centroid(&moving), centroid(&fixed));
+        let centred = |positions: &[[f64; 3]], center: &[f64; 3]| -> Vec<[f64; 3]> {
+            positions
+                .iter()
+                .map(|position| [0, 1, 2].map(|k| position[k] - center[k]))
+                .collect()
+        };
+
+        let (rotation, rmsd) = best_rotation(
+            &centred(&moving, &moving_center),
+            &centred(&fixed, &fixed_center),
+        );
+
+        let rotated_center: [f64; 3] =
+            rotation.map(|row| (0..3).map(|k| row[k] * moving_center[k]).sum());
+        let translation = [0, 1, 2].map(|k| fixed_center[k] - rotated_center[k]);
+
+        Ok(Superposition {
+            rotation: rotation.map(|row| row.map(|value| value as f32)),
+            translation: translation.map(|value| value as f32),
+            rmsd: rmsd as f32,
+        })
+    }
+
+    /// Move the atoms, and every frame of a trajectory, by a superposition. The lattice
+    /// and the properties known to be vectors, such as forces, are rotated with them.
+    pub fn transform(&mut self, superposition: &Superposition) {
+        for atom in &mut self.atoms {
+            atom.position = superposition.apply(&atom.position);
+        }
+        self.lattice = self
+            .lattice
+            .map(|lattice| lattice.map(|vector| superposition.rotate(&vector)));
+        rotate_vectors(&mut self.properties, superposition);
+
+        for frame in &mut self.frames {
+            for position in &mut frame.positions {
+                *position = superposition.apply(position);
+            }
+            frame.lattice = frame
+                .lattice
+                .map(|lattice| lattice.map(|vector| superposition.rotate(&vector)));
+            rotate_vectors(&mut frame.properties, superposition);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::molecule::{Atom, Element};
+
+    fn molecule(positions: &[[f32; 3]]) -> Molecule {
+        let atoms = positions
+            .iter()
+            .map(|&position| Atom::new(Element::Carbon, position))
+            .collect();
+        Molecule::new(atoms, Vec::new(), String::new())
+    }
+
+    fn close(first: &[f32; 3], second: &[f32; 3]) -> bool {
+        first.sub(second).mag() < 1e-4
+    }
+
+    #[test]
+    fn superposes_a_moved_copy() {
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.5, 0.0, 0.0],
+            [1.5, 2.0, 0.0],
+            [-0.5, 1.0, 3.0],
+            [2.0, -1.0, 1.0],
+        ];
+
+        // A third of a turn about (1, 1, 1), which cycles the axes, then a shift
+        let moved: Vec<[f32; 3]> = positions
+            .iter()
+            .map(|&[x, y, z]| [z + 4.0, x - 2.0, y + 0.5])
+            .collect();
+
+        let reference = molecule(&positions);
+        let mut moving = molecule(&moved);
+        let superposition = moving.superposition(&reference, None).unwrap();
+        assert!(superposition.rmsd < 1e-3);
+
+        moving.transform(&superposition);
+        for (atom, position) in moving.atoms.iter().zip(&positions) {
+            assert!(close(&atom.position, position));
+        }
+    }
+
+    #[test]
+    fn measures_the_rmsd_of_the_pairs() {
+        let reference = molecule(&[[-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], [9.0, 9.0, 9.0]]);
+        let moving = molecule(&[[0.0, 5.0, 2.0], [0.0, 5.0, -2.0]]);
+
+        let superposition = moving
+            .superposition(&reference, Some(&[(0, 1), (1, 0)]))
+            .unwrap();
+        assert!((superposition.rmsd - 1.0).abs() < 1e-4);
+        assert!(close(&superposition.apply(&[0.0, 5.0, 0.0]), &[0.0; 3]));
+    }
+
+    #[test]
+    fn rejects_bad_mappings() {
+        let reference = molecule(&[[0.0; 3], [1.0, 0.0, 0.0]]);
+        let moving = molecule(&[[0.0; 3]]);
+
+        let error = |mapping| moving.superposition(&reference, mapping).unwrap_err();
+        assert_eq!(error(None), "Cannot pair 1 atoms with 2 by index");
+        assert_eq!(error(Some(&[])), "No atoms to superpose");
+        assert_eq!(error(Some(&[(1, 0)])), "No atom 1 to superpose");
+        assert_eq!(error(Some(&[(0, 2)])), "No atom 2 in the reference");
+    }
+}
@@ -0,0 +1,354 @@
+use std::iter::Enumerate;
+use std::str::Lines;
+
+use super::{
+    apply_charges, connect_by_distance, split_fields, Atom, AtomProperty, Bond, BondOrder, Element,
+    Frame, Molecule, ParseError,
+};
+
+type NumberedLines<'a> = Enumerate<Lines<'a>>;
+
+/// Columns of a plain XYZ file, in the notation of the extended XYZ `Properties` key
+const DEFAULT_PROPERTIES: &str = "species:S:1:pos:R:3";
+
+/// One block of a XYZ file: the atom count, the comment line and the atoms
+struct Block {
+    name: String,
+    atoms: Vec<Atom>,
+    frame: Frame,
+}
+
+/// A `key=value` pair of an extended XYZ comment line, with the column of the key
+struct KeyValue {
+    column: usize,
+    key: String,
+    value: String,
+}
+
+/// Split the comment line of an extended XYZ file into its `key=value` pairs. Values may
+/// be quoted or wrapped in braces, and keys without a value are flags set to `T`.
+fn key_values(comment: &str) -> Vec<KeyValue> {
+    let mut pairs = Vec::<KeyValue>::new();
+    let mut chars = comment.char_indices().peekable();
+
+    loop {
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+        let column = chars.peek().map_or(comment.len(), |(i, _)| *i) + 1;
+        let key: String =
+            std::iter::from_fn(|| chars.next_if(|(_, c)| *c != '=' && !c.is_whitespace()))
+                .map(|(_, c)| c)
+                .collect();
+        if key.is_empty() {
+            break;
+        }
+
+        if chars.next_if(|(_, c)| *c == '=').is_none() {
+            let value = "T".to_owned();
+            pairs.push(KeyValue { column, key, value });
+            continue;
+        }
+
+        let value: String = match chars.peek() {
+            Some((_, '"')) | Some((_, '{')) => {
+                let close = if chars.next().map(|(_, c)| c) == Some('"') {
+                    '"'
+                } else {
+                    '}'
+                };
+                let value = std::iter::from_fn(|| chars.next_if(|(_, c)| *c != close))
+                    .map(|(_, c)| c)
+                    .collect();
+                chars.next();
+                value
+            }
+            _ => std::iter::from_fn(|| chars.next_if(|(_, c)| !c.is_whitespace()))
+                .map(|(_, c)| c)
+                .collect(),
+        };
+
+        pairs.push(KeyValue { column, key, value });
+    }
+
+    pairs
+}
+
+fn read_lattice(value: &str) -> Result<[[f32; 3]; 3], String> {
+    let values = value
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| format!("Expected numbers in Lattice, found '{}'", value))?;
+
+    match values.as_slice() {
+        [ax, ay, az, bx, by, bz, cx, cy, cz] => {
+            Ok([[*ax, *ay, *az], [*bx, *by, *bz], [*cx, *cy, *cz]])
+        }
+        _ => Err(format!("Lattice needs 9 values, found {}", values.len())),
+    }
+}
+
+/// A column group of the `Properties` key, such as `forces:R:3`
+struct Column {
+    name: String,
+    kind: String,
+    count: usize,
+}
+
+fn read_columns(properties: &str) -> Result<Vec<Column>, String> {
+    let fields: Vec<&str> = properties.split(':').collect();
+    let malformed = || format!("Malformed Properties '{}'", properties);
+
+    if !fields.len().is_multiple_of(3) {
+        return Err(malformed());
+    }
+
+    fields
+        .chunks(3)
+        .map(|field| {
+            Ok(Column {
+                name: field[0].to_owned(),
+                kind: field[1].to_ascii_uppercase(),
+                count: field[2].parse().map_err(|_| malformed())?,
+            })
+        })
+        .collect()
+}
+
+/// Read the atom count which starts a block, along with its line number, returning
+/// `None` once the file is exhausted
+fn read_count(lines: &mut NumberedLines) -> Result<Option<(usize, usize)>, ParseError> {
+    let (number, count_line) = match lines.find(|(_, line)| !line.trim().is_empty()) {
+        Some((index, line)) => (index + 1, line),
+        None => return Ok(None),
+    };
+
+    let count = split_fields(count_line, number)[0].parse("the number of atoms")?;
+    Ok(Some((number, count)))
+}
+
+/// Read the comment line and atoms of a block of `total_lines` atoms
+fn read_block(
+    lines: &mut NumberedLines,
+    number: usize,
+    total_lines: usize,
+) -> Result<Block, ParseError> {
+    let (comment_number, name) = lines
+        .next()
+        .map(|(index, line)| (index + 1, line))
+        .ok_or_else(|| ParseError::new(number + 1, 1, "Missing comment line"))?;
+
+    let mut frame = Frame::default();
+    let mut columns = read_columns(DEFAULT_PROPERTIES).unwrap_or_default();
+
+    for KeyValue { column, key, value } in key_values(name) {
+        let result = match key.as_str() {
+            "Lattice" => read_lattice(&value).map(|lattice| frame.lattice = Some(lattice)),
+            "Properties" => read_columns(&value).map(|properties| columns = properties),
+            _ => {
+                frame.info.insert(key, value);
+                Ok(())
+            }
+        };
+
+        result.map_err(|reason| ParseError::new(comment_number, column, reason))?;
+    }
+
+    // Plain XYZ files have a free form title rather than key/value pairs
+    if !name.contains('=') {
+        frame.info.clear();
+    }
+
+    for column in &columns {
+        if column.kind != "S" && column.name != "pos" {
+            frame.properties.push(AtomProperty {
+                name: column.name.clone(),
+                columns: column.count,
+                values: Vec::with_capacity(total_lines * column.count),
+            });
+        }
+    }
+
+    let mut atoms = Vec::<Atom>::with_capacity(total_lines);
+
+    for (index, line) in lines.take(total_lines) {
+        let number = index + 1;
+        let mut fields = split_fields(line, number).into_iter();
+        let mut element = Element::Other;
+        let mut position = [0.0; 3];
+        let mut properties = frame.properties.iter_mut();
+
+        for column in &columns {
+            let values: Vec<_> = (&mut fields).take(column.count).collect();
+            if values.len() != column.count {
+                let what = format!("'{}' values", column.name);
+                return Err(ParseError::missing(number, line, &what));
+            }
+
+            match (column.name.as_str(), column.kind.as_str()) {
+                ("species", "S") => element = Element::from(values[0].text),
+                ("pos", _) => {
+                    for (coordinate, value) in position.iter_mut().zip(&values) {
+                        *coordinate = value.parse("a coordinate")?;
+                    }
+                }
+                (_, "S") => {}
+                (_, kind) => {
+                    let property = properties.next().ok_or_else(|| {
+                        ParseError::new(number, values[0].column, "Unexpected column")
+                    })?;
+
+                    for value in values {
+                        property.values.push(match (kind, value.text) {
+                            ("L", "T") | ("L", "True") => 1.0,
+                            ("L", _) => 0.0,
+                            _ => value.parse("a number")?,
+                        });
+                    }
+                }
+            }
+        }
+
+        atoms.push(Atom::new(element, position));
+    }
+
+    if atoms.len() < total_lines {
+        return Err(ParseError::new(
+            comment_number + atoms.len() + 1,
+            1,
+            format!(
+                "Incomplete block, expected {} atoms but found {}",
+                total_lines,
+                atoms.len()
+            ),
+        ));
+    }
+
+    frame.positions = atoms.iter().map(|atom| atom.position).collect();
+    apply_charges(&mut atoms, &frame.properties);
+
+    Ok(Block {
+        name: name.to_owned(),
+        atoms,
+        frame,
+    })
+}
+
+/// Frames of a trajectory must hold the same elements in the same order
+/// Whether a line holds an element and three coordinates, as an atom of a block does
+fn is_atom_record(line: &str) -> bool {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    fields.len() >= 4
+        && fields[1..4]
+            .iter()
+            .all(|field| field.parse::<f32>().is_ok())
+}
+
+fn same_species(first: &[Atom], second: &[Atom]) -> bool {
+    first.len() == second.len()
+        && first
+            .iter()
+            .zip(second)
+            .all(|(a, b)| a.element == b.element)
+}
+
+fn build_xyz_molecule(first: Block, frames: Vec<Frame>) -> Molecule {
+    let bonds = connect_by_distance(&first.atoms)
+        .into_iter()
+        .map(|(i, j)| Bond::new(i, j, BondOrder::Unknown))
+        .collect();
+
+    let mut molecule = Molecule::new(first.atoms, bonds, first.name);
+    molecule.lattice = first.frame.lattice;
+    molecule.info = first.frame.info;
+    molecule.properties = first.frame.properties;
+
+    if frames.len() > 1 {
+        molecule.frames = frames;
+    }
+
+    molecule
+}
+
+/// Read a plain or extended XYZ file. Consecutive blocks with the same elements are
+/// read as the frames of a trajectory, using the name of the first frame. A block with
+/// different atoms, as found in extended XYZ datasets, starts a new `Molecule`.
+pub fn read_xyz(file: &str) -> Result<Vec<Molecule>, ParseError> {
+    let mut lines = file.lines().enumerate();
+    let mut molecules = Vec::<Molecule>::new();
+
+    let mut first = match read_count(&mut lines)? {
+        Some((number, count)) => read_block(&mut lines, number, count)?,
+        None => return Ok(molecules),
+    };
+    let mut frames = vec![first.frame.clone()];
+
+    loop {
+        let (number, count) = match read_count(&mut lines) {
+            Ok(Some(found)) => found,
+            Ok(None) => break,
+            // Files whose atom count is one short leave a single atom record behind
+            // the last block
+            Err(error)
+                if is_atom_record(file.lines().nth(error.line - 1).unwrap_or(""))
+                    && lines.clone().all(|(_, line)| line.trim().is_empty()) =>
+            {
+                break
+            }
+            Err(error) => return Err(error),
+        };
+
+        let block = read_block(&mut lines, number, count)?;
+        if same_species(&first.atoms, &block.atoms) {
+            frames.push(block.frame);
+            continue;
+        }
+
+        molecules.push(build_xyz_molecule(first, frames));
+        frames = vec![block.frame.clone()];
+        first = block;
+    }
+
+    molecules.push(build_xyz_molecule(first, frames));
+
+    Ok(molecules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HYDROGEN: &str = "2\nhydrogen\nH 0.0 0.0 0.0\nH 0.74 0.0 0.0\n";
+
+    fn error(file: &str) -> (usize, usize) {
+        let error = read_xyz(file).err().unwrap();
+        (error.line, error.column)
+    }
+
+    #[test]
+    fn reads_frames() {
+        let file = format!("{}{}", HYDROGEN, HYDROGEN);
+        let molecules = read_xyz(&file).unwrap();
+
+        assert_eq!(molecules.len(), 1);
+        assert_eq!(molecules[0].frames.len(), 2);
+    }
+
+    #[test]
+    fn tolerates_one_atom_left_behind() {
+        let file = format!("{}H 1.0 1.0 1.0\n\n", HYDROGEN);
+        assert_eq!(read_xyz(&file).unwrap()[0].atoms.len(), 2);
+    }
+
+    #[test]
+    fn reports_malformed_blocks() {
+        assert_eq!(error(&format!("{}foo\n", HYDROGEN)), (5, 1));
+        assert_eq!(
+            error(&format!("{}H 1.0 1.0 1.0\nH 1.0 1.0 1.0\n", HYDROGEN)),
+            (5, 1)
+        );
+        assert_eq!(error(&format!("{}2\n", HYDROGEN)), (6, 1));
+        assert_eq!(error(&format!("{}2\nc\nH 0.0 0.0 0.0\n", HYDROGEN)), (8, 1));
+        assert_eq!(error("1\ncomment\nH 0.0 x 0.0\n"), (3, 7));
+    }
+}
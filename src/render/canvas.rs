@@ -66,10 +66,25 @@ fn init_canvas(
     Ok(canvas)
 }
 
+/// The position of a pointer in pixels of the canvas, from its top left corner, however
+/// large the canvas is shown on the page
+fn canvas_position(canvas: &HtmlCanvasElement, client_x: i32, client_y: i32) -> (i32, i32) {
+    #![allow(clippy::cast_possible_truncation)]
+
+    let rect = canvas.get_bounding_client_rect();
+    let scale_x = f64::from(canvas.width()) / rect.width().max(1.0);
+    let scale_y = f64::from(canvas.height()) / rect.height().max(1.0);
+
+    (
+        ((f64::from(client_x) - rect.left()) * scale_x) as i32,
+        ((f64::from(client_y) - rect.top()) * scale_y) as i32,
+    )
+}
+
 fn attach_mouse_down_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Result<(), JsValue> {
+    let target = canvas.clone();
     let handler = move |event: web_sys::MouseEvent| {
-        let x = event.client_x();
-        let y = event.client_y();
+        let (x, y) = canvas_position(&target, event.client_x(), event.client_y());
         if let Ok(mut store) = app.store.try_borrow_mut() {
             store.msg(&Msg::MouseDown(x, y));
         }
@@ -99,10 +114,10 @@ fn attach_mouse_up_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Result<(
 }
 
 fn attach_mouse_move_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Result<(), JsValue> {
+    let target = canvas.clone();
     let handler = move |event: web_sys::MouseEvent| {
         event.prevent_default();
-        let x = event.client_x();
-        let y = event.client_y();
+        let (x, y) = canvas_position(&target, event.client_x(), event.client_y());
         if let Ok(mut store) = app.store.try_borrow_mut() {
             store.msg(&Msg::MouseMove(x, y));
         }
@@ -136,10 +151,10 @@ fn attach_mouse_wheel_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Resul
 }
 
 fn attach_touch_start_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Result<(), JsValue> {
+    let target = canvas.clone();
     let handler = move |event: web_sys::TouchEvent| {
         let touch = event.touches().item(0).expect("First Touch");
-        let x = touch.client_x();
-        let y = touch.client_y();
+        let (x, y) = canvas_position(&target, touch.client_x(), touch.client_y());
         if let Ok(mut store) = app.store.try_borrow_mut() {
             store.msg(&Msg::MouseDown(x, y));
         }
@@ -153,11 +168,11 @@ fn attach_touch_start_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Resul
 }
 
 fn attach_touch_move_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Result<(), JsValue> {
+    let target = canvas.clone();
     let handler = move |event: web_sys::TouchEvent| {
         event.prevent_default();
         let touch = event.touches().item(0).expect("First Touch");
-        let x = touch.client_x();
-        let y = touch.client_y();
+        let (x, y) = canvas_position(&target, touch.client_x(), touch.client_y());
         if let Ok(mut store) = app.store.try_borrow_mut() {
             store.msg(&Msg::MouseMove(x, y));
         }
@@ -171,7 +186,9 @@ fn attach_touch_move_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Result
 }
 
 fn attach_touch_end_handler(canvas: &HtmlCanvasElement, app: Rc<App>) -> Result<(), JsValue> {
-    let handler = move |_event: web_sys::TouchEvent| {
+    let handler = move |event: web_sys::TouchEvent| {
+        // Keep the browser from following up with mouse events, which would click twice
+        event.prevent_default();
         if let Ok(mut store) = app.store.try_borrow_mut() {
             store.msg(&Msg::MouseUp);
         }
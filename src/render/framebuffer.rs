@@ -0,0 +1,138 @@
+use wasm_bindgen::prelude::*;
+use web_sys::WebGl2RenderingContext as GL;
+use web_sys::*;
+
+/// A color and a depth buffer to render into instead of the canvas
+pub struct Framebuffer {
+    framebuffer: WebGlFramebuffer,
+    color: WebGlRenderbuffer,
+    depth: WebGlRenderbuffer,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Framebuffer {
+    pub fn new(gl: &WebGl2RenderingContext, width: i32, height: i32) -> Result<Self, JsValue> {
+        Self::multisampled(gl, width, height, 0)
+    }
+
+    /// A framebuffer keeping `samples` of each pixel to smooth the edges of what is
+    /// drawn, which must be resolved into one without samples before being read
+    pub fn multisampled(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+        samples: i32,
+    ) -> Result<Self, JsValue> {
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or_else(|| JsValue::from_str("Could not create a framebuffer"))?;
+
+        let renderbuffer = |format: u32| -> Result<WebGlRenderbuffer, JsValue> {
+            let renderbuffer = gl
+                .create_renderbuffer()
+                .ok_or_else(|| JsValue::from_str("Could not create a renderbuffer"))?;
+            gl.bind_renderbuffer(GL::RENDERBUFFER, Some(&renderbuffer));
+            gl.renderbuffer_storage_multisample(GL::RENDERBUFFER, samples, format, width, height);
+            Ok(renderbuffer)
+        };
+
+        let color = renderbuffer(GL::RGBA8)?;
+        let depth = renderbuffer(GL::DEPTH_COMPONENT24)?;
+
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_renderbuffer(
+            GL::FRAMEBUFFER,
+            GL::COLOR_ATTACHMENT0,
+            GL::RENDERBUFFER,
+            Some(&color),
+        );
+        gl.framebuffer_renderbuffer(
+            GL::FRAMEBUFFER,
+            GL::DEPTH_ATTACHMENT,
+            GL::RENDERBUFFER,
+            Some(&depth),
+        );
+
+        let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+        gl.bind_renderbuffer(GL::RENDERBUFFER, None);
+
+        if status != GL::FRAMEBUFFER_COMPLETE {
+            return Err(JsValue::from_str(&format!(
+                "Could not create a {}x{} framebuffer",
+                width, height
+            )));
+        }
+
+        Ok(Self {
+            framebuffer,
+            color,
+            depth,
+            width,
+            height,
+        })
+    }
+
+    /// Draw into this framebuffer until `unbind` is called
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, self.width, self.height);
+    }
+
+    /// Free the memory of the buffers straight away, rather than when they are
+    /// collected as garbage
+    pub fn delete(self, gl: &WebGl2RenderingContext) {
+        gl.delete_framebuffer(Some(&self.framebuffer));
+        gl.delete_renderbuffer(Some(&self.color));
+        gl.delete_renderbuffer(Some(&self.depth));
+    }
+
+    /// Average the samples of each pixel into `target`, which is as large
+    pub fn resolve(&self, gl: &WebGl2RenderingContext, target: &Self) {
+        gl.bind_framebuffer(GL::READ_FRAMEBUFFER, Some(&self.framebuffer));
+        gl.bind_framebuffer(GL::DRAW_FRAMEBUFFER, Some(&target.framebuffer));
+        gl.blit_framebuffer(
+            0,
+            0,
+            self.width,
+            self.height,
+            0,
+            0,
+            target.width,
+            target.height,
+            GL::COLOR_BUFFER_BIT,
+            GL::NEAREST,
+        );
+    }
+
+    /// Draw onto the canvas again
+    pub fn unbind(gl: &WebGl2RenderingContext) {
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+        gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
+    }
+
+    /// The RGBA bytes of a rectangle of pixels, row by row from the bottom left
+    pub fn read(
+        &self,
+        gl: &WebGl2RenderingContext,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<Vec<u8>, JsValue> {
+        #![allow(clippy::cast_sign_loss)]
+
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        gl.read_pixels_with_opt_u8_array(
+            x,
+            y,
+            width,
+            height,
+            GL::RGBA,
+            GL::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        )?;
+        Ok(pixels)
+    }
+}
@@ -2,24 +2,258 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 
 use crate::app::State;
+use crate::molecule::{Bond, BondOrder, Element, Molecule, Representation, Ring};
 use wasm_bindgen::prelude::*;
 use web_sys::WebGl2RenderingContext as GL;
 use web_sys::*;
 
 pub mod canvas;
 
+mod framebuffer;
+use framebuffer::Framebuffer;
+
+mod pick;
+
+mod png;
+
 mod shader;
 
 mod shape;
 use shape::Render;
 
-use shape::cylinder;
-use shape::sphere;
+mod snapshot;
+
+use shape::lines;
+use shape::quad;
+
+/// Length of a force arrow, in Angstroms, per unit of force
+const FORCE_SCALE: f32 = 1.0;
+
+/// Radius of the circle drawn inside an aromatic ring, relative to the ring itself
+const AROMATIC_CIRCLE_SCALE: f32 = 0.55;
+
+/// Number of cylinders making up the circle of an aromatic ring
+const AROMATIC_CIRCLE_SEGMENTS: usize = 24;
+
+/// Color of the sky behind the molecules
+const BACKGROUND_COLOR: [f32; 4] = [0.53, 0.8, 0.98, 1.0];
+
+const HYDROGEN_BOND_COLOR: [f32; 4] = [0.1, 0.6, 1.0, 1.0];
+
+const MEASUREMENT_COLOR: [f32; 4] = [1.0, 0.85, 0.1, 1.0];
+
+/// Color of the translucent halo around each selected atom
+const SELECTION_COLOR: [f32; 4] = [0.2, 1.0, 0.4, 0.35];
+
+/// How far the halo of a selected atom reaches beyond the atom, in Angstroms
+const SELECTION_HALO: f32 = 0.2;
+
+/// Height of capitals and digits in every label, in Angstroms
+const LABEL_HEIGHT: f32 = 0.3;
+
+/// Radius of both the atoms and the bonds of the licorice representation
+const LICORICE_RADIUS: f32 = 0.15;
+
+/// Half the size of the cross marking an atom without bonds in a wireframe
+const WIREFRAME_CROSS: f32 = 0.15;
+
+/// Length of each dash of a dashed line, and of the gaps between them, in Angstroms
+const DASH_LENGTH: f32 = 0.15;
+const DASH_GAP: f32 = 0.1;
+
+/// Split the line from `start` to `end` into dashes, centred so both ends look alike.
+/// Ends in the same place leave nothing to dash.
+fn dashes(start: &[f32; 3], end: &[f32; 3]) -> Vec<[[f32; 3]; 2]> {
+    #![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #![allow(clippy::cast_precision_loss)]
+    use webgl_matrix::Vector;
+
+    let line = end.sub(start);
+    let length = line.mag();
+    if length <= f32::EPSILON {
+        return Vec::new();
+    }
+
+    let count = ((length + DASH_GAP) / (DASH_LENGTH + DASH_GAP))
+        .floor()
+        .max(1.0) as usize;
+    let used = count as f32 * (DASH_LENGTH + DASH_GAP) - DASH_GAP;
+    let direction = line.scale(1.0 / length);
+    let first = start.add(&direction.scale((length - used).max(0.0) / 2.0));
+
+    (0..count)
+        .map(|i| {
+            let dash_start = first.add(&direction.scale(i as f32 * (DASH_LENGTH + DASH_GAP)));
+            [
+                dash_start,
+                dash_start.add(&direction.scale(DASH_LENGTH.min(length))),
+            ]
+        })
+        .collect()
+}
+
+/// Dash a bond being formed or broken, giving each dash the color of the nearer atom
+fn partial_bond_dashes(molecule: &Molecule, atoms: [usize; 2]) -> Vec<([[f32; 3]; 2], [f32; 4])> {
+    use webgl_matrix::Vector;
+
+    let first = &molecule.atoms[atoms[0]];
+    let second = &molecule.atoms[atoms[1]];
+    let middle = first.position.add(&second.position).scale(0.5);
+
+    dashes(&first.position, &second.position)
+        .into_iter()
+        .map(|dash| {
+            let nearer = if dash[0].sub(&middle).dot(&first.position.sub(&middle)) > 0.0 {
+                first
+            } else {
+                second
+            };
+            (dash, nearer.element.cpk_color())
+        })
+        .collect()
+}
+
+/// Radius of the sphere drawn for an atom, or `None` when it has none
+fn atom_radius(representation: Representation, element: Element) -> Option<f32> {
+    match representation {
+        Representation::Spacefill => Some(element.vdw_radius()),
+        Representation::BallAndStick => Some(element.covalent_radius() * 0.5),
+        Representation::Licorice => Some(LICORICE_RADIUS),
+        Representation::Wireframe => None,
+    }
+}
+
+/// Radius of the cylinder drawn for a single bond, or `None` when bonds are not drawn
+/// as sticks
+fn stick_radius(representation: Representation, first: Element, second: Element) -> Option<f32> {
+    match representation {
+        Representation::BallAndStick => {
+            Some((first.covalent_radius().min(second.covalent_radius()) / 8.0).max(0.05))
+        }
+        Representation::Licorice => Some(LICORICE_RADIUS),
+        Representation::Spacefill | Representation::Wireframe => None,
+    }
+}
+
+/// The twelve edges of the unit cell spanned by the lattice vectors
+fn cell_edges(lattice: &[[f32; 3]; 3]) -> Vec<[[f32; 3]; 2]> {
+    use webgl_matrix::Vector;
+
+    let corner = |i: f32, j: f32, k: f32| {
+        lattice[0]
+            .scale(i)
+            .add(&lattice[1].scale(j))
+            .add(&lattice[2].scale(k))
+    };
+
+    let mut edges = Vec::with_capacity(12);
+    for &(i, j) in &[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+        edges.push([corner(0.0, i, j), corner(1.0, i, j)]);
+        edges.push([corner(i, 0.0, j), corner(i, 1.0, j)]);
+        edges.push([corner(i, j, 0.0), corner(i, j, 1.0)]);
+    }
+
+    edges
+}
+
+/// A unit vector perpendicular to a bond, along which the cylinders of double and
+/// triple bonds are spread. It lies in the plane of a neighbouring atom when there is
+/// one, so that the bonds of a ring are drawn in the plane of the ring.
+fn bond_normal(molecule: &Molecule, neighbors: &[Vec<usize>], bond: &Bond) -> [f32; 3] {
+    use webgl_matrix::Vector;
+
+    let [first, second] = bond.atoms;
+    let start = molecule.atoms[first].position;
+    let axis = molecule.atoms[second].position.sub(&start);
+    let axis = axis.scale(1.0 / axis.mag());
+
+    let in_plane = neighbors[first]
+        .iter()
+        .filter(|&&atom| atom != second)
+        .chain(neighbors[second].iter().filter(|&&atom| atom != first))
+        .map(|&atom| molecule.atoms[atom].position.sub(&start));
+
+    // Fall back on whichever axis is furthest from the bond
+    let fallback = if axis[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+
+    for direction in in_plane.chain(std::iter::once(fallback)) {
+        let normal = direction.sub(&axis.scale(direction.dot(&axis)));
+        if normal.mag() > 1e-3 {
+            return normal.scale(1.0 / normal.mag());
+        }
+    }
+
+    fallback
+}
+
+/// Points around the circle drawn inside an aromatic ring, in the plane of the ring.
+/// The first point is repeated at the end to close the circle.
+fn aromatic_circle(molecule: &Molecule, ring: &Ring) -> Vec<[f32; 3]> {
+    #![allow(clippy::cast_precision_loss)]
+    use webgl_matrix::Vector;
+
+    let center = ring.center(&molecule.atoms);
+    let normal = ring.normal(&molecule.atoms);
+
+    let radius = ring
+        .atoms
+        .iter()
+        .map(|&atom| molecule.atoms[atom].position.sub(&center).mag())
+        .sum::<f32>()
+        / ring.atoms.len() as f32
+        * AROMATIC_CIRCLE_SCALE;
+
+    // Two perpendicular directions in the plane, towards the first two atoms
+    let in_plane = |atom: usize, others: &[[f32; 3]]| {
+        let mut direction = molecule.atoms[atom].position.sub(&center);
+        for other in others {
+            direction = direction.sub(&other.scale(direction.dot(other)));
+        }
+        direction.scale(1.0 / direction.mag())
+    };
+    let u = in_plane(ring.atoms[0], &[normal]);
+    let v = in_plane(ring.atoms[1], &[normal, u]);
+
+    (0..=AROMATIC_CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / AROMATIC_CIRCLE_SEGMENTS as f32;
+            center
+                .add(&u.scale(radius * angle.cos()))
+                .add(&v.scale(radius * angle.sin()))
+        })
+        .collect()
+}
+
+/// Blend translucent colors over what is drawn, keeping the alpha of the result right
+/// when the background is transparent, so that the colors are premultiplied by it
+fn enable_blending(gl: &WebGl2RenderingContext) {
+    gl.enable(GL::BLEND);
+    gl.blend_func_separate(
+        GL::SRC_ALPHA,
+        GL::ONE_MINUS_SRC_ALPHA,
+        GL::ONE,
+        GL::ONE_MINUS_SRC_ALPHA,
+    );
+}
 
 pub struct WebRenderer {
     shader_sys: shader::System,
-    sphere_object: sphere::VBO,
-    cylinder_object: cylinder::VBO,
+    /// Drawn for every sphere and cylinder, which are ray-cast within it
+    quad_object: quad::VBO,
+    /// Per-instance attributes of the spheres and cylinders, filled again every frame
+    sphere_instances: WebGlBuffer,
+    cylinder_instances: WebGlBuffer,
+    hydrogen_bond_instances: WebGlBuffer,
+    text_instances: WebGlBuffer,
+    /// Signed distance field of every glyph of the font, baked when building
+    glyph_atlas: WebGlTexture,
+    line_buffers: lines::Buffers,
+    /// The single offscreen pixel in which atoms and bonds are drawn to be picked
+    pick_buffer: Framebuffer,
     vaos: RefCell<HashMap<String, web_sys::WebGlVertexArrayObject>>,
 }
 
@@ -27,24 +261,52 @@ impl WebRenderer {
     pub fn new(gl: &WebGl2RenderingContext) -> Self {
         let shader_sys = shader::System::new(gl);
 
-        let sphere_object = sphere::VBO::new(20, 20);
+        let quad_object = quad::VBO::new();
 
-        let cylinder_object = cylinder::VBO::new(30);
+        let sphere_instances = gl.create_buffer().unwrap();
+        let cylinder_instances = gl.create_buffer().unwrap();
+        let hydrogen_bond_instances = gl.create_buffer().unwrap();
+        let text_instances = gl.create_buffer().unwrap();
+
+        let glyph_atlas = shape::text::create_atlas(gl).unwrap();
+
+        let line_buffers = lines::Buffers::new(gl);
+
+        let pick_buffer = Framebuffer::new(gl, 1, 1).unwrap();
 
         Self {
             shader_sys,
-            sphere_object,
-            cylinder_object,
+            quad_object,
+            sphere_instances,
+            cylinder_instances,
+            hydrogen_bond_instances,
+            text_instances,
+            glyph_atlas,
+            line_buffers,
+            pick_buffer,
             vaos: RefCell::new(HashMap::new()),
         }
     }
 
-    pub fn render(&mut self, gl: &WebGl2RenderingContext, state: &State) -> Result<(), JsValue> {
-        use shape::cylinder::Cylinder;
-        use shape::sphere::Sphere;
+    pub fn render(&self, gl: &WebGl2RenderingContext, state: &State) -> Result<(), JsValue> {
+        self.draw(gl, state, &BACKGROUND_COLOR)
+    }
+
+    /// Draw the scene over a background, into the canvas or whichever framebuffer is
+    /// bound
+    fn draw(
+        &self,
+        gl: &WebGl2RenderingContext,
+        state: &State,
+        background: &[f32; 4],
+    ) -> Result<(), JsValue> {
+        use shape::cylinder::Cylinders;
+        use shape::lines::Lines;
+        use shape::sphere::Spheres;
+        use shape::text::Text;
         use shape::triangle::Triangle;
 
-        gl.clear_color(0.53, 0.8, 0.98, 1.);
+        gl.clear_color(background[0], background[1], background[2], background[3]);
         gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
 
         // Display a 'debug' triangle to orient the size of a 1.0 step and location of the origin.
@@ -64,63 +326,310 @@ impl WebRenderer {
 
         let molecules = state.molecules();
 
-        self.shader_sys.use_program(gl, shader::Kind::Cylinder);
-        let mut new_cylinder = Cylinder {
-            object: &self.cylinder_object,
+        let mut new_cylinders = Cylinders {
+            object: &self.quad_object,
+            buffer: &self.cylinder_instances,
             shader: self.shader_sys.get_shader(shader::Kind::Cylinder).unwrap(),
-            color_start: [1.0, 0.0, 0.0, 1.0],
-            color_end: [1.0, 0.0, 0.0, 1.0],
-            radius: 0.05,
-            position_start: [0.0, 0.0, 0.0],
-            position_end: [0.0, 0.0, 0.0],
+            instances: Vec::new(),
+            unlit: false,
+            projection: None,
         };
-        self.prepare_for_render(gl, &new_cylinder, "cylinder");
 
-        for molecule in molecules {
+        let with_sticks = |molecule: &&Molecule| {
+            matches!(
+                molecule.representation,
+                Representation::BallAndStick | Representation::Licorice
+            )
+        };
+
+        for molecule in molecules.iter().filter(with_sticks) {
+            use webgl_matrix::Vector;
+
+            let licorice = molecule.representation == Representation::Licorice;
+            let neighbors = molecule.neighbors();
+
             for bond in &molecule.bonds {
-                let atom1 = &bond[0];
-                let atom2 = &bond[1];
+                let atom1 = &molecule.atoms[bond.atoms[0]];
+                let atom2 = &molecule.atoms[bond.atoms[1]];
+
+                let color1 = atom1.element.cpk_color();
+                let color2 = atom2.element.cpk_color();
 
-                new_cylinder.position_start = atom1.position;
-                new_cylinder.position_end = atom2.position;
+                let radius =
+                    match stick_radius(molecule.representation, atom1.element, atom2.element) {
+                        Some(radius) => radius,
+                        None => continue,
+                    };
 
-                new_cylinder.color_start = atom1.element.cpk_color();
-                new_cylinder.color_end = atom2.element.cpk_color();
+                // Multiple bonds are drawn as thinner parallel cylinders, spaced in
+                // units of the radius of a single bond
+                let offsets: &[f32] = match bond.order {
+                    _ if licorice => &[0.0],
+                    BondOrder::Double => &[-1.0, 1.0],
+                    BondOrder::Triple => &[-2.0, 0.0, 2.0],
+                    _ => &[0.0],
+                };
 
-                new_cylinder.radius = atom1
-                    .element
-                    .covalent_radius()
-                    .min(atom2.element.covalent_radius())
-                    / 8.0;
+                let (normal, thickness) = if offsets.len() > 1 {
+                    (
+                        bond_normal(molecule, &neighbors, bond).scale(radius),
+                        radius * 0.6,
+                    )
+                } else {
+                    ([0.0, 0.0, 0.0], radius)
+                };
 
-                if new_cylinder.radius < 0.05 {
-                    new_cylinder.radius = 0.05;
+                for offset in offsets {
+                    let shift = normal.scale(*offset);
+                    new_cylinders.push(
+                        &atom1.position.add(&shift),
+                        &atom2.position.add(&shift),
+                        thickness,
+                        &color1,
+                        &color2,
+                    );
                 }
+            }
+
+            // Bonds being formed or broken are drawn as thin dashes
+            for &[i, j] in &molecule.partial_bonds {
+                let (first, second) = (molecule.atoms[i].element, molecule.atoms[j].element);
+                let radius = match stick_radius(molecule.representation, first, second) {
+                    Some(radius) => radius,
+                    None => continue,
+                };
 
-                new_cylinder.render(gl, state);
+                for ([start, end], color) in partial_bond_dashes(molecule, [i, j]) {
+                    new_cylinders.push(&start, &end, radius * 0.5, &color, &color);
+                }
             }
         }
 
-        self.shader_sys.use_program(gl, shader::Kind::Sphere);
-        let mut new_sphere = Sphere {
-            object: &self.sphere_object,
+        for molecule in molecules.iter().filter(with_sticks) {
+            let color = [0.25, 0.25, 0.25, 1.0];
+
+            for ring in molecule.rings.iter().filter(|ring| ring.aromatic) {
+                for segment in aromatic_circle(molecule, ring).windows(2) {
+                    new_cylinders.push(&segment[0], &segment[1], 0.04, &color, &color);
+                }
+            }
+        }
+
+        // Measurements are dashed between consecutive atoms, and labelled with their
+        // values along with the other labels
+        for measurement in state.measurements() {
+            let color = MEASUREMENT_COLOR;
+            for pair in measurement.positions(molecules).windows(2) {
+                for [start, end] in dashes(&pair[0], &pair[1]) {
+                    new_cylinders.push(&start, &end, 0.025, &color, &color);
+                }
+            }
+        }
+
+        for molecule in molecules {
+            use webgl_matrix::Vector;
+
+            if let Some(lattice) = &molecule.lattice {
+                let color = [0.2, 0.2, 0.2, 1.0];
+                for [start, end] in cell_edges(lattice) {
+                    new_cylinders.push(&start, &end, 0.02, &color, &color);
+                }
+            }
+
+            let forces = molecule
+                .property("forces")
+                .filter(|forces| forces.columns == 3);
+
+            if let Some(forces) = forces {
+                let color = [1.0, 0.0, 1.0, 1.0];
+                for (atom, force) in molecule.atoms.iter().zip(forces.values.chunks(3)) {
+                    let arrow = [force[0], force[1], force[2]].scale(FORCE_SCALE);
+                    if arrow.mag() < 0.01 {
+                        continue;
+                    }
+
+                    let end = atom.position.add(&arrow);
+                    new_cylinders.push(&atom.position, &end, 0.04, &color, &color);
+                }
+            }
+        }
+
+        self.shader_sys.use_program(gl, shader::Kind::Cylinder);
+        self.prepare_for_render(gl, &new_cylinders, "cylinder");
+        new_cylinders.render(gl, state);
+
+        // Hydrogen bonds are dashed between the hydrogen and the acceptor, in a pass of
+        // their own
+        let mut hydrogen_bonds = Cylinders {
+            object: &self.quad_object,
+            buffer: &self.hydrogen_bond_instances,
+            shader: self.shader_sys.get_shader(shader::Kind::Cylinder).unwrap(),
+            instances: Vec::new(),
+            unlit: false,
+            projection: None,
+        };
+
+        for group in state.hydrogen_bonds() {
+            let donors = &molecules[group.donors];
+            let acceptors = &molecules[group.acceptors];
+
+            for bond in &group.bonds {
+                let hydrogen = donors.atoms[bond.hydrogen].position;
+                let acceptor = acceptors.atoms[bond.acceptor].position;
+
+                for [start, end] in dashes(&hydrogen, &acceptor) {
+                    let color = HYDROGEN_BOND_COLOR;
+                    hydrogen_bonds.push(&start, &end, 0.03, &color, &color);
+                }
+            }
+        }
+
+        self.prepare_for_render(gl, &hydrogen_bonds, "hydrogen_bonds");
+        hydrogen_bonds.render(gl, state);
+
+        let mut new_spheres = Spheres {
+            object: &self.quad_object,
+            buffer: &self.sphere_instances,
             shader: self.shader_sys.get_shader(shader::Kind::Sphere).unwrap(),
-            radius: 1.0,
-            color: [0.0, 0.0, 0.0, 0.0],
-            position: [0.0, 0.0, 0.0],
+            instances: Vec::new(),
+            unlit: false,
+            projection: None,
         };
-        self.prepare_for_render(gl, &new_sphere, "sphere");
 
         for molecule in molecules {
             for atom in &molecule.atoms {
-                new_sphere.color = atom.element.cpk_color();
-                new_sphere.position = atom.position;
-                new_sphere.radius = atom.element.covalent_radius() * 0.5;
+                if let Some(radius) = atom_radius(molecule.representation, atom.element) {
+                    new_spheres.push(&atom.position, radius, &atom.element.cpk_color());
+                }
+            }
+        }
+
+        self.shader_sys.use_program(gl, shader::Kind::Sphere);
+        self.prepare_for_render(gl, &new_spheres, "sphere");
+        new_spheres.render(gl, state);
 
-                new_sphere.render(gl, state);
+        // Wireframes are drawn with lines, each half of a bond in the color of its atom
+        self.shader_sys.use_program(gl, shader::Kind::Basic);
+        let mut new_lines = Lines {
+            buffers: &self.line_buffers,
+            shader: self.shader_sys.get_shader(shader::Kind::Basic).unwrap(),
+            verticies: Vec::new(),
+            colors: Vec::new(),
+        };
+
+        for molecule in molecules
+            .iter()
+            .filter(|molecule| molecule.representation == Representation::Wireframe)
+        {
+            use webgl_matrix::Vector;
+
+            for bond in &molecule.bonds {
+                let atom1 = &molecule.atoms[bond.atoms[0]];
+                let atom2 = &molecule.atoms[bond.atoms[1]];
+                let middle = atom1.position.add(&atom2.position).scale(0.5);
+
+                new_lines.push(&atom1.position, &middle, &atom1.element.cpk_color());
+                new_lines.push(&middle, &atom2.position, &atom2.element.cpk_color());
+            }
+
+            for &atoms in &molecule.partial_bonds {
+                for ([start, end], color) in partial_bond_dashes(molecule, atoms) {
+                    new_lines.push(&start, &end, &color);
+                }
+            }
+
+            for (atom, bonded) in molecule.atoms.iter().zip(molecule.neighbors()) {
+                if !bonded.is_empty() {
+                    continue;
+                }
+
+                for axis in &[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]] {
+                    let arm = axis.scale(WIREFRAME_CROSS);
+                    let color = atom.element.cpk_color();
+                    new_lines.push(&atom.position.sub(&arm), &atom.position.add(&arm), &color);
+                }
+            }
+
+            for ring in molecule.rings.iter().filter(|ring| ring.aromatic) {
+                for segment in aromatic_circle(molecule, ring).windows(2) {
+                    new_lines.push(&segment[0], &segment[1], &[0.25, 0.25, 0.25, 1.0]);
+                }
             }
         }
 
+        self.prepare_for_render(gl, &new_lines, "lines");
+        new_lines.render(gl, state);
+
+        // Selected atoms are wrapped in a translucent halo, drawn last so that the
+        // atoms show through it
+        if !state.selection().is_empty() {
+            let mut halos = Spheres {
+                object: &self.quad_object,
+                buffer: &self.sphere_instances,
+                shader: self.shader_sys.get_shader(shader::Kind::Sphere).unwrap(),
+                instances: Vec::new(),
+                unlit: true,
+                projection: None,
+            };
+
+            for id in state.selection().iter() {
+                let molecule = &molecules[id.molecule];
+                let atom = &molecule.atoms[id.atom];
+                let radius =
+                    atom_radius(molecule.representation, atom.element).unwrap_or(WIREFRAME_CROSS);
+                halos.push(&atom.position, radius + SELECTION_HALO, &SELECTION_COLOR);
+            }
+
+            enable_blending(gl);
+            gl.depth_mask(false);
+
+            self.shader_sys.use_program(gl, shader::Kind::Sphere);
+            self.prepare_for_render(gl, &halos, "sphere");
+            halos.render(gl, state);
+
+            gl.depth_mask(true);
+            gl.disable(GL::BLEND);
+        }
+
+        // Labels are written over everything else, so that atoms never hide them
+        let mut text = Text {
+            object: &self.quad_object,
+            buffer: &self.text_instances,
+            atlas: &self.glyph_atlas,
+            shader: self.shader_sys.get_shader(shader::Kind::Text).unwrap(),
+            instances: Vec::new(),
+            height: LABEL_HEIGHT,
+        };
+
+        {
+            use webgl_matrix::Vector;
+
+            // Lift the value of a measurement off its dashed line
+            let view = state.camera().view();
+            let up = [view[1], view[5], view[9]];
+
+            for measurement in state.measurements() {
+                let anchor = measurement
+                    .label_position(molecules)
+                    .add(&up.scale(LABEL_HEIGHT));
+                text.push(&anchor, &measurement.label(molecules), &MEASUREMENT_COLOR);
+            }
+        }
+
+        for label in state.labels() {
+            text.push(&label.position(molecules), &label.text, &label.color);
+        }
+
+        gl.disable(GL::DEPTH_TEST);
+        enable_blending(gl);
+
+        self.shader_sys.use_program(gl, shader::Kind::Text);
+        self.prepare_for_render(gl, &text, "text");
+        text.render(gl, state);
+
+        gl.disable(GL::BLEND);
+        gl.enable(GL::DEPTH_TEST);
+
         Ok(())
     }
 
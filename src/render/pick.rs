@@ -0,0 +1,154 @@
+use wasm_bindgen::prelude::*;
+use web_sys::WebGl2RenderingContext as GL;
+use web_sys::*;
+
+use super::framebuffer::Framebuffer;
+use super::shape::cylinder::Cylinders;
+use super::shape::sphere::Spheres;
+use super::shape::Render;
+use super::{atom_radius, shader, stick_radius, WebRenderer, WIREFRAME_CROSS};
+use crate::app::{AtomId, Pick, State};
+use crate::molecule::Representation;
+
+/// Radius of the cylinders standing in for the bonds of a wireframe, whose lines are
+/// too thin to point at
+const WIREFRAME_PICK_RADIUS: f32 = 0.08;
+
+/// The color drawn for the target at `index`. Black is left for the background.
+fn encode(index: usize) -> [f32; 4] {
+    #![allow(clippy::cast_precision_loss)]
+
+    let id = index + 1;
+    let byte = |shift: usize| ((id >> shift) & 0xff) as f32 / 255.0;
+    [byte(0), byte(8), byte(16), 1.0]
+}
+
+/// The index of the target drawn in a pixel, or `None` for the background
+fn decode(pixel: &[u8]) -> Option<usize> {
+    let id = usize::from(pixel[0]) | usize::from(pixel[1]) << 8 | usize::from(pixel[2]) << 16;
+    id.checked_sub(1)
+}
+
+/// Narrow `projection` to the pixel at `x` and `row` of a `width` by `height` canvas,
+/// so it fills a viewport of a single pixel
+fn pixel_projection(
+    projection: &[f32; 16],
+    x: i32,
+    row: i32,
+    width: i32,
+    height: i32,
+) -> [f32; 16] {
+    #![allow(clippy::cast_precision_loss)]
+
+    let (width, height) = (width as f32, height as f32);
+
+    // The center of the pixel in normalized device coordinates
+    let center_x = 2.0 * (x as f32 + 0.5) / width - 1.0;
+    let center_y = 2.0 * (row as f32 + 0.5) / height - 1.0;
+
+    // Scale the clip coordinates of every column about that center, leaving the depth
+    let mut narrowed = *projection;
+    for column in narrowed.chunks_exact_mut(4) {
+        column[0] = width * (column[0] - center_x * column[3]);
+        column[1] = height * (column[1] - center_y * column[3]);
+    }
+    narrowed
+}
+
+impl WebRenderer {
+    /// Find the atom or bond drawn at a point of the canvas, in pixels from its top
+    /// left corner, by drawing each of them in a color of its own into an offscreen
+    /// pixel, through a projection narrowed to the point. Atoms
+    /// of a wireframe can be picked as far as their crosses reach, while the bonds of
+    /// a spacefill are hidden and cannot be picked at all.
+    pub fn pick(
+        &self,
+        gl: &WebGl2RenderingContext,
+        state: &State,
+        x: i32,
+        y: i32,
+    ) -> Result<Option<Pick>, JsValue> {
+        let width = gl.drawing_buffer_width();
+        let height = gl.drawing_buffer_height();
+
+        // Rows of the canvas count from the bottom
+        let row = height - 1 - y;
+        if x < 0 || x >= width || row < 0 || row >= height {
+            return Ok(None);
+        }
+
+        let projection = pixel_projection(&state.camera().projection(), x, row, width, height);
+
+        let mut targets = Vec::new();
+
+        let mut cylinders = Cylinders {
+            object: &self.quad_object,
+            buffer: &self.cylinder_instances,
+            shader: self.shader_sys.get_shader(shader::Kind::Cylinder).unwrap(),
+            instances: Vec::new(),
+            unlit: true,
+            projection: Some(projection),
+        };
+
+        let mut spheres = Spheres {
+            object: &self.quad_object,
+            buffer: &self.sphere_instances,
+            shader: self.shader_sys.get_shader(shader::Kind::Sphere).unwrap(),
+            instances: Vec::new(),
+            unlit: true,
+            projection: Some(projection),
+        };
+
+        for (index, molecule) in state.molecules().iter().enumerate() {
+            let representation = molecule.representation;
+
+            for (atom_index, atom) in molecule.atoms.iter().enumerate() {
+                let radius = atom_radius(representation, atom.element).unwrap_or(WIREFRAME_CROSS);
+                spheres.push(&atom.position, radius, &encode(targets.len()));
+                targets.push(Pick::Atom(AtomId {
+                    molecule: index,
+                    atom: atom_index,
+                }));
+            }
+
+            if representation == Representation::Spacefill {
+                continue;
+            }
+
+            for (bond_index, bond) in molecule.bonds.iter().enumerate() {
+                let first = &molecule.atoms[bond.atoms[0]];
+                let second = &molecule.atoms[bond.atoms[1]];
+
+                let radius = stick_radius(representation, first.element, second.element)
+                    .unwrap_or(WIREFRAME_PICK_RADIUS);
+                let color = encode(targets.len());
+
+                cylinders.push(&first.position, &second.position, radius, &color, &color);
+                targets.push(Pick::Bond {
+                    molecule: index,
+                    bond: bond_index,
+                });
+            }
+        }
+
+        let buffer = &self.pick_buffer;
+        buffer.bind(gl);
+
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+
+        self.shader_sys.use_program(gl, shader::Kind::Cylinder);
+        self.prepare_for_render(gl, &cylinders, "cylinder");
+        cylinders.render(gl, state);
+
+        self.shader_sys.use_program(gl, shader::Kind::Sphere);
+        self.prepare_for_render(gl, &spheres, "sphere");
+        spheres.render(gl, state);
+
+        let pixel = buffer.read(gl, 0, 0, 1, 1);
+
+        Framebuffer::unbind(gl);
+
+        Ok(decode(&pixel?).and_then(|index| targets.get(index).copied()))
+    }
+}
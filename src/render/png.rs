@@ -0,0 +1,332 @@
+//! A PNG encoder for snapshots of the scene, compressing with the fixed Huffman codes
+//! of deflate, which is plenty for the large flat areas of a rendered molecule
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Size of the window in which deflate looks back for repeated bytes
+const WINDOW: usize = 32768;
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// Earlier positions tried for each match, trading speed for smaller images
+const MAX_CHAIN: usize = 16;
+
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASES: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+const DISTANCE_BASES: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Encode RGBA pixels, row by row from the top left, as a PNG image
+pub fn encode(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Eight bits for each of red, green, blue and alpha, compressed with deflate,
+    // filtered row by row and not interlaced
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &zlib(&filter(width, rgba)));
+    chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    #![allow(clippy::cast_possible_truncation)]
+
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut table = [0_u32; 256];
+    for (n, entry) in (0..).zip(table.iter_mut()) {
+        *entry = (0..8).fold(n, |c, _| {
+            if c & 1 == 1 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            }
+        });
+    }
+
+    !bytes.iter().fold(!0_u32, |crc, &byte| {
+        table[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+
+    let mut a = 1_u32;
+    let mut b = 0_u32;
+
+    // The largest number of bytes which cannot overflow the sums between reductions
+    for block in bytes.chunks(5552) {
+        for &byte in block {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+
+    b << 16 | a
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let (a, b, c) = (i16::from(left), i16::from(up), i16::from(up_left));
+    let p = a + b - c;
+    let (pa, pb, pc) = ((p - a).abs(), (p - b).abs(), (p - c).abs());
+
+    if pa <= pb && pa <= pc {
+        left
+    } else if pb <= pc {
+        up
+    } else {
+        up_left
+    }
+}
+
+/// Prefix each row with whichever of the five PNG filters leaves the smallest
+/// differences between neighbouring pixels, which then compress best
+fn filter(width: u32, rgba: &[u8]) -> Vec<u8> {
+    #![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+
+    let stride = width as usize * 4;
+    let mut filtered = Vec::with_capacity(rgba.len() + rgba.len() / stride.max(1));
+
+    let blank = vec![0; stride];
+    let mut previous = &blank[..];
+    let mut candidate = vec![0; stride];
+    let mut best = vec![0; stride];
+
+    for row in rgba.chunks_exact(stride) {
+        let mut best_kind = 0;
+        let mut best_score = u64::MAX;
+
+        for kind in 0..5 {
+            for i in 0..stride {
+                let left = if i >= 4 { row[i - 4] } else { 0 };
+                let up = previous[i];
+                let up_left = if i >= 4 { previous[i - 4] } else { 0 };
+
+                let prediction = match kind {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                    _ => paeth(left, up, up_left),
+                };
+                candidate[i] = row[i].wrapping_sub(prediction);
+            }
+
+            let score = candidate
+                .iter()
+                .map(|&byte| u64::from((byte as i8).unsigned_abs()))
+                .sum();
+            if score < best_score {
+                best_score = score;
+                best_kind = kind;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+
+        filtered.push(best_kind);
+        filtered.extend_from_slice(&best);
+        previous = row;
+    }
+
+    filtered
+}
+
+/// Wrap the compressed data in the zlib stream PNG expects
+fn zlib(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window, and check bits making the header a multiple of 31
+    let mut stream = vec![0x78, 0x9C];
+    stream.extend(deflate(data));
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    /// Write the lowest `count` bits of `value`, least significant first
+    fn bits(&mut self, value: u32, count: u32) {
+        #![allow(clippy::cast_possible_truncation)]
+
+        self.buffer |= value << self.count;
+        self.count += count;
+
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Write a Huffman code, which deflate packs most significant bit first
+    fn code(&mut self, code: u32, length: u32) {
+        self.bits(code.reverse_bits() >> (32 - length), length);
+    }
+
+    /// Write a literal byte, the end of the block or the start of a length with its
+    /// fixed code
+    fn symbol(&mut self, symbol: u32) {
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xC0 + symbol - 280, 8),
+        }
+    }
+
+    /// Repeat `length` bytes from `distance` bytes back
+    fn repeat(&mut self, length: usize, distance: usize) {
+        #![allow(clippy::cast_possible_truncation)]
+
+        let index = LENGTH_BASES.partition_point(|&base| base <= length) - 1;
+        self.symbol(257 + index as u32);
+        self.bits(
+            (length - LENGTH_BASES[index]) as u32,
+            LENGTH_EXTRA_BITS[index],
+        );
+
+        let index = DISTANCE_BASES.partition_point(|&base| base <= distance) - 1;
+        self.code(index as u32, 5);
+        self.bits(
+            (distance - DISTANCE_BASES[index]) as u32,
+            DISTANCE_EXTRA_BITS[index],
+        );
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        // Pad the last byte with zeros
+        self.bits(0, 7);
+        self.bytes
+    }
+}
+
+const NONE: usize = usize::MAX;
+
+/// Earlier positions of the data, chained by the hash of the three bytes found there
+struct Chains {
+    head: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl Chains {
+    fn new() -> Self {
+        Self {
+            head: vec![NONE; 1 << HASH_BITS],
+            previous: vec![NONE; WINDOW],
+        }
+    }
+
+    fn hash(data: &[u8], i: usize) -> usize {
+        let bytes = u32::from(data[i]) << 16 | u32::from(data[i + 1]) << 8 | u32::from(data[i + 2]);
+        (bytes.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, data: &[u8], i: usize) {
+        if i + MIN_MATCH <= data.len() {
+            let hash = Self::hash(data, i);
+            self.previous[i % WINDOW] = self.head[hash];
+            self.head[hash] = i;
+        }
+    }
+
+    /// The length and distance of the longest repeat of the bytes at `i`, if any
+    fn longest_match(&self, data: &[u8], i: usize) -> (usize, usize) {
+        let limit = (data.len() - i).min(MAX_MATCH);
+        if limit < MIN_MATCH {
+            return (0, 0);
+        }
+
+        let mut best = (0, 0);
+        let mut candidate = self.head[Self::hash(data, i)];
+        let mut chain = MAX_CHAIN;
+
+        while candidate != NONE && i - candidate <= WINDOW && chain > 0 {
+            let length = data[candidate..]
+                .iter()
+                .zip(&data[i..i + limit])
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if length > best.0 {
+                best = (length, i - candidate);
+                if length == limit {
+                    break;
+                }
+            }
+
+            candidate = self.previous[candidate % WINDOW];
+            chain -= 1;
+        }
+
+        best
+    }
+}
+
+/// Compress data as a single block with the fixed codes, finding repeats through
+/// chains of earlier positions starting with the same three bytes
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+
+    // The final block, with fixed codes
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+
+    let mut chains = Chains::new();
+
+    let mut i = 0;
+    while i < data.len() {
+        let (length, distance) = chains.longest_match(data, i);
+
+        if length >= MIN_MATCH {
+            writer.repeat(length, distance);
+            for j in i..i + length {
+                chains.insert(data, j);
+            }
+            i += length;
+        } else {
+            writer.symbol(u32::from(data[i]));
+            chains.insert(data, i);
+            i += 1;
+        }
+    }
+
+    writer.symbol(256);
+    writer.finish()
+}
@@ -0,0 +1,70 @@
+#version 300 es
+
+precision highp float;
+
+in vec4 rayPoint;
+in vec3 cylinderStart;
+in vec3 cylinderEnd;
+in float cylinderRadius;
+in vec4 colorStart;
+in vec4 colorEnd;
+
+uniform mat4 perspective;
+uniform mat4 view;
+uniform vec3 cameraPos;
+// Write the color of each instance as it is, such as the colors identifying what is
+// drawn where when picking
+uniform bool unlit;
+
+out vec4 o_col;
+
+vec4 shade(vec3 position, vec3 normal, vec4 color, vec3 cameraPosition);
+
+void main() {
+    // Cast a ray from the camera onto the sides or the flat ends of the cylinder
+    vec3 direction = normalize(rayPoint.xyz / rayPoint.w - cameraPos);
+
+    vec3 axis = cylinderEnd - cylinderStart;
+    vec3 fromStart = cameraPos - cylinderStart;
+    float axis2 = dot(axis, axis);
+    float axisDirection = dot(axis, direction);
+    float axisStart = dot(axis, fromStart);
+
+    float k2 = axis2 - axisDirection * axisDirection;
+    float k1 = axis2 * dot(fromStart, direction) - axisStart * axisDirection;
+    float k0 = axis2 * dot(fromStart, fromStart) - axisStart * axisStart
+        - cylinderRadius * cylinderRadius * axis2;
+
+    float h = k1 * k1 - k2 * k0;
+    if (h < 0.0) {
+        discard;
+    }
+    h = sqrt(h);
+
+    float t = (-k1 - h) / k2;
+    float along = axisStart + t * axisDirection;
+    vec3 normal;
+
+    if (along > 0.0 && along < axis2) {
+        normal = (fromStart + t * direction - axis * along / axis2) / cylinderRadius;
+    } else {
+        t = ((along < 0.0 ? 0.0 : axis2) - axisStart) / axisDirection;
+        if (abs(k1 + k2 * t) >= h) {
+            discard;
+        }
+        normal = axis * sign(along) / sqrt(axis2);
+        along = clamp(along, 0.0, axis2);
+    }
+
+    if (t < 0.0) {
+        discard;
+    }
+
+    vec3 hit = cameraPos + direction * t;
+    vec4 clip = perspective * view * vec4(hit, 1.0);
+    gl_FragDepth = clip.z / clip.w * 0.5 + 0.5;
+
+    vec4 color = along < axis2 * 0.5 ? colorStart : colorEnd;
+    vec4 shaded = shade(hit, normal, color, cameraPos);
+    o_col = unlit ? color : shaded;
+}
@@ -1,33 +1,67 @@
 #version 300 es
 
-in vec3 position;
-uniform vec4 color_start;
-uniform vec4 color_end;
-uniform float radius;
-uniform float height;
+// A corner of the quad drawn for each cylinder, from (-1, -1) to (1, 1)
+in vec2 corner;
+
+// One of each per cylinder
+in vec3 start;
+in vec3 end;
+in float radius;
+in vec4 color_start;
+in vec4 color_end;
 
 uniform mat4 perspective;
-uniform mat4 model;
 uniform mat4 view;
 
-out vec3 normal;
-out vec4 worldPosition;
+out vec4 rayPoint;
+out vec3 cylinderStart;
+out vec3 cylinderEnd;
+out float cylinderRadius;
+out vec4 colorStart;
+out vec4 colorEnd;
+
+void main() {
+    vec3 axis = end - start;
+    vec3 w = length(axis) > 1e-6 ? normalize(axis) : vec3(0.0, 0.0, 1.0);
 
-uniform vec3 cameraPos;
-out vec3 fromFragmentToCamera;
+    // Any two directions perpendicular to the axis and to each other
+    vec3 helper = abs(w.z) < 0.9 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 u = normalize(cross(helper, w));
+    vec3 v = cross(w, u);
 
-out vec4 col;
+    // The quad is the rectangle of the screen covering the box around the cylinder,
+    // or the whole screen when the box reaches behind the camera
+    mat4 transform = perspective * view;
+    vec2 low = vec2(1.0);
+    vec2 high = vec2(-1.0);
+    bool behind = false;
 
-void main() {
-    worldPosition = model * vec4(position.xy * radius, position.z * height, 1.0);
-    gl_Position = perspective *  view * worldPosition;
+    for (int i = 0; i < 8; i++) {
+        vec3 point = (i < 4 ? start : end)
+            + u * radius * ((i & 1) == 0 ? -1.0 : 1.0)
+            + v * radius * ((i & 2) == 0 ? -1.0 : 1.0);
+        vec4 clip = transform * vec4(point, 1.0);
 
-    normal = (model * vec4(position, 0.0)).xyz;
-    fromFragmentToCamera = cameraPos - worldPosition.xyz;
+        behind = behind || clip.w <= 0.0;
+        low = min(low, clip.xy / clip.w);
+        high = max(high, clip.xy / clip.w);
+    }
 
-    if (position.z > 0.0) {
-        col = color_end;
-    } else {
-        col = color_start;
+    if (behind) {
+        low = vec2(-1.0);
+        high = vec2(1.0);
     }
+
+    vec2 screen = mix(max(low, -1.0), min(high, 1.0), corner * 0.5 + 0.5);
+    gl_Position = vec4(screen, 0.0, 1.0);
+
+    // A point along the ray through this corner, kept homogeneous so that it is
+    // interpolated linearly across the screen
+    rayPoint = inverse(transform) * vec4(screen, 0.0, 1.0);
+
+    cylinderStart = start;
+    cylinderEnd = end;
+    cylinderRadius = radius;
+    colorStart = color_start;
+    colorEnd = color_end;
 }
@@ -1,19 +1,12 @@
-#version 300 es
 
-precision highp float;
-
-in vec4 worldPosition;
-in vec3 normal;
-in vec3 fromFragmentToCamera;
-
-in vec4 col;
-out vec4 o_col;
-
-void main() {
-    if (dot(worldPosition, vec4(0.0, 1.0, 0.0, 10.0)) < 0.0) {
+// Light a point of a surface as seen from the camera, with the light at the camera
+vec4 shade(vec3 position, vec3 normal, vec4 color, vec3 cameraPosition) {
+    if (position.y < -10.0) {
         discard;
     }
 
+    vec3 fromFragmentToCamera = cameraPosition - position;
+
     float shininess = 1.0;
     vec3 sunlightColor = vec3(1.0, 1.0, 1.0);
     vec3 sunlightDir = -normalize(fromFragmentToCamera);
@@ -30,5 +23,5 @@ void main() {
 
     vec4 lighting = vec4(ambient + diffuse + specular, 1.0);
 
-    o_col = col * lighting;
+    return color * lighting;
 }
@@ -7,9 +7,14 @@ static BASIC_VS: &str = include_str!("./basic_vs.glsl");
 static BASIC_FS: &str = include_str!("./basic_fs.glsl");
 
 static SPHERE_VS: &str = include_str!("./sphere_vs.glsl");
+static SPHERE_FS: &str = include_str!("./sphere_fs.glsl");
 static CYLINDER_VS: &str = include_str!("./cylinder_vs.glsl");
+static CYLINDER_FS: &str = include_str!("./cylinder_fs.glsl");
+static TEXT_VS: &str = include_str!("./text_vs.glsl");
+static TEXT_FS: &str = include_str!("./text_fs.glsl");
 
-static LIGHTING_FS: &str = include_str!("./lighting_fs.glsl");
+/// The `shade` function, appended to the fragment shaders which declare it
+static LIGHTING: &str = include_str!("./lighting.glsl");
 
 /// Identifiers for our different shaders
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
@@ -17,6 +22,7 @@ pub enum Kind {
     Basic,
     Sphere,
     Cylinder,
+    Text,
 }
 
 /// Powers retrieving and using our shaders
@@ -32,9 +38,12 @@ impl System {
 
         let basic_shader = Shader::new(gl, BASIC_VS, BASIC_FS).unwrap();
 
-        let sphere_shader = Shader::new(gl, SPHERE_VS, LIGHTING_FS).unwrap();
+        let sphere_shader = Shader::new(gl, SPHERE_VS, &[SPHERE_FS, LIGHTING].concat()).unwrap();
 
-        let cylinder_shader = Shader::new(gl, CYLINDER_VS, LIGHTING_FS).unwrap();
+        let cylinder_shader =
+            Shader::new(gl, CYLINDER_VS, &[CYLINDER_FS, LIGHTING].concat()).unwrap();
+
+        let text_shader = Shader::new(gl, TEXT_VS, TEXT_FS).unwrap();
 
         let active_program = RefCell::new(Kind::Basic);
         gl.use_program(Some(&basic_shader.program));
@@ -42,6 +51,7 @@ impl System {
         programs.insert(Kind::Basic, basic_shader);
         programs.insert(Kind::Sphere, sphere_shader);
         programs.insert(Kind::Cylinder, cylinder_shader);
+        programs.insert(Kind::Text, text_shader);
 
         Self {
             programs,
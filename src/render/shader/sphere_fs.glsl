@@ -0,0 +1,39 @@
+#version 300 es
+
+precision highp float;
+
+in vec3 worldPosition;
+in vec3 sphereCenter;
+in float sphereRadius;
+in vec4 col;
+
+uniform mat4 perspective;
+uniform mat4 view;
+uniform vec3 cameraPos;
+// Write the color of each instance as it is, such as the colors identifying what is
+// drawn where when picking
+uniform bool unlit;
+
+out vec4 o_col;
+
+vec4 shade(vec3 position, vec3 normal, vec4 color, vec3 cameraPosition);
+
+void main() {
+    // Cast a ray from the camera through this point of the quad onto the sphere
+    vec3 direction = normalize(worldPosition - cameraPos);
+    vec3 fromCenter = cameraPos - sphereCenter;
+
+    float b = dot(direction, fromCenter);
+    float c = dot(fromCenter, fromCenter) - sphereRadius * sphereRadius;
+    float discriminant = b * b - c;
+    if (discriminant < 0.0) {
+        discard;
+    }
+
+    vec3 hit = cameraPos + direction * (-b - sqrt(discriminant));
+    vec4 clip = perspective * view * vec4(hit, 1.0);
+    gl_FragDepth = clip.z / clip.w * 0.5 + 0.5;
+
+    vec4 shaded = shade(hit, (hit - sphereCenter) / sphereRadius, col, cameraPos);
+    o_col = unlit ? col : shaded;
+}
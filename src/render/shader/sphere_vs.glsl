@@ -1,27 +1,41 @@
 #version 300 es
 
-// Defines the sphere itself
-in vec3 position;
-uniform vec4 color;
-uniform float radius;
+// A corner of the quad drawn for each sphere, from (-1, -1) to (1, 1)
+in vec2 corner;
+
+// One of each per sphere
+in vec3 center;
+in float radius;
+in vec4 color;
 
 uniform mat4 perspective;
-uniform mat4 model;
 uniform mat4 view;
-
-out vec3 normal;
-out vec4 worldPosition;
-
 uniform vec3 cameraPos;
-out vec3 fromFragmentToCamera;
 
+out vec3 worldPosition;
+out vec3 sphereCenter;
+out float sphereRadius;
 out vec4 col;
 
 void main() {
-    worldPosition = model * vec4(position * radius, 1.0);
-    gl_Position = perspective *  view * worldPosition;
+    // The quad faces the camera through the center of the sphere, and is just large
+    // enough to hold its outline in perspective
+    vec3 toCenter = center - cameraPos;
+    float range = length(toCenter);
+    vec3 forward = toCenter / range;
+
+    vec3 up = vec3(view[0][1], view[1][1], view[2][1]);
+    vec3 right = normalize(cross(forward, up));
+    up = cross(right, forward);
+
+    float size = range > radius * 1.01
+        ? radius * range / sqrt(range * range - radius * radius)
+        : 0.0;
+
+    worldPosition = center + (right * corner.x + up * corner.y) * size;
+    gl_Position = perspective * view * vec4(worldPosition, 1.0);
 
-    normal = position;
-    fromFragmentToCamera = cameraPos - worldPosition.xyz;
+    sphereCenter = center;
+    sphereRadius = radius;
     col = color;
 }
@@ -0,0 +1,27 @@
+#version 300 es
+
+precision highp float;
+
+in vec2 uv;
+in vec4 col;
+
+// Signed distance field of the glyphs, at 0.5 on the edge of each stroke
+uniform sampler2D atlas;
+
+out vec4 o_col;
+
+// Where the dark outline keeping labels readable on any background ends
+const float OUTLINE = 0.38;
+
+void main() {
+    float field = texture(atlas, uv).r;
+    float smoothing = fwidth(field);
+
+    float fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, field);
+    float coverage = smoothstep(OUTLINE - smoothing, OUTLINE + smoothing, field);
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    o_col = vec4(col.rgb * fill, col.a * coverage);
+}
@@ -0,0 +1,36 @@
+#version 300 es
+
+// A corner of the quad drawn for each glyph, from (-1, -1) to (1, 1)
+in vec2 corner;
+
+// One of each per glyph
+in vec3 anchor;
+// Bottom left corner of the glyph, in world units from the anchor facing the camera
+in vec2 offset;
+// Top left corner of the cell of the glyph in the atlas
+in vec2 cell;
+in vec4 color;
+
+uniform mat4 perspective;
+uniform mat4 view;
+// Size of a glyph in world units, and of its cell in the atlas
+uniform vec2 glyphSize;
+uniform vec2 cellSize;
+
+out vec2 uv;
+out vec4 col;
+
+void main() {
+    vec2 along = corner * 0.5 + 0.5;
+
+    vec3 right = vec3(view[0][0], view[1][0], view[2][0]);
+    vec3 up = vec3(view[0][1], view[1][1], view[2][1]);
+
+    vec2 facing = offset + along * glyphSize;
+    vec3 position = anchor + right * facing.x + up * facing.y;
+    gl_Position = perspective * view * vec4(position, 1.0);
+
+    // Rows of the atlas run down from the top of each glyph
+    uv = cell + vec2(along.x, 1.0 - along.y) * cellSize;
+    col = color;
+}
@@ -1,91 +1,48 @@
 use web_sys::WebGl2RenderingContext as GL;
 use web_sys::*;
-use webgl_matrix::{Mat4, Matrix, Vec3, Vector};
 
 use crate::app::State;
 use crate::render::shader::Kind;
 use crate::render::shader::Shader;
+use crate::render::shape::quad::VBO;
 use crate::render::shape::Render;
 
-fn cross(u: &Vec3, v: &Vec3) -> Vec3 {
-    /*
-     *  i  -j   k
-     * u0  u1  u2
-     * v0  v1  v2
-     */
-    [
-        u[1] * v[2] - u[2] * v[1],
-        u[2] * v[0] - u[0] * v[2],
-        u[0] * v[1] - u[1] * v[0],
-    ]
-}
-
-pub struct VBO {
-    pub verticies: Vec<f32>,
-    pub indicies: Vec<u16>,
-}
+/// Floats for each cylinder: both ends, the radius and the color of each half
+const STRIDE: i32 = 15;
 
-pub struct Cylinder<'a, 'b> {
+/// Every cylinder of a pass, each ray-cast within an instance of the quad, in one call
+pub struct Cylinders<'a, 'b> {
     pub object: &'b VBO,
+    /// Filled with the `instances` on every render
+    pub buffer: &'b WebGlBuffer,
     pub shader: &'a Shader,
-    pub radius: f32,
-    pub color_start: [f32; 4],
-    pub color_end: [f32; 4],
-    pub position_start: [f32; 3],
-    pub position_end: [f32; 3],
+    pub instances: Vec<f32>,
+    /// Draw the colors as they are, without lighting, such as when picking
+    pub unlit: bool,
+    /// Replaces the projection of the camera, such as by one narrowed to the pixel
+    /// being picked
+    pub projection: Option<[f32; 16]>,
 }
 
-impl VBO {
-    pub fn new(slices: u16) -> Self {
-        #![allow(clippy::identity_op)]
-
-        use std::f32::consts::PI;
-
-        let mut vertex_position_data = Vec::<f32>::new();
-        let mut index_data = Vec::<u16>::new();
-
-        // Extra value is to force completion of the circle
-        for i in 0..slices {
-            let theta = f32::from(i) * 2.0 * PI / f32::from(slices);
-            let sin_theta = theta.sin();
-            let cos_theta = theta.cos();
-
-            // Index on top circle (0, 2, 4, 6, 8...)
-            vertex_position_data.push(cos_theta);
-            vertex_position_data.push(sin_theta);
-            vertex_position_data.push(0.5);
-
-            // Index on bottom circle (1, 3, 5, 7, 9...)
-            vertex_position_data.push(cos_theta);
-            vertex_position_data.push(sin_theta);
-            vertex_position_data.push(-0.5);
-
-            // Rectangle on side
-            index_data.push(i * 2 + 0);
-            index_data.push(i * 2 + 2);
-            index_data.push(i * 2 + 1);
-
-            index_data.push(i * 2 + 2);
-            index_data.push(i * 2 + 1);
-            index_data.push(i * 2 + 3);
-        }
-
-        vertex_position_data.push(1.0);
-        vertex_position_data.push(0.0);
-        vertex_position_data.push(0.5);
-
-        vertex_position_data.push(1.0);
-        vertex_position_data.push(0.0);
-        vertex_position_data.push(-0.5);
-
-        Self {
-            verticies: vertex_position_data,
-            indicies: index_data,
-        }
+impl Cylinders<'_, '_> {
+    /// A cylinder whose halves take the colors of the nearer end
+    pub fn push(
+        &mut self,
+        start: &[f32; 3],
+        end: &[f32; 3],
+        radius: f32,
+        color_start: &[f32; 4],
+        color_end: &[f32; 4],
+    ) {
+        self.instances.extend_from_slice(start);
+        self.instances.extend_from_slice(end);
+        self.instances.push(radius);
+        self.instances.extend_from_slice(color_start);
+        self.instances.extend_from_slice(color_end);
     }
 }
 
-impl<'a, 'b> Render<'a> for Cylinder<'a, 'b> {
+impl<'a> Render<'a> for Cylinders<'a, '_> {
     fn shader_kind() -> Kind {
         Kind::Cylinder
     }
@@ -99,63 +56,39 @@ impl<'a, 'b> Render<'a> for Cylinder<'a, 'b> {
 
         let shader = self.shader();
 
-        let pos_attrib = gl.get_attrib_location(&shader.program, "position");
-        gl.enable_vertex_attrib_array(pos_attrib as u32);
+        let corner_attrib = gl.get_attrib_location(&shader.program, "corner");
+        gl.enable_vertex_attrib_array(corner_attrib as u32);
 
-        Cylinder::buffer_f32_data(gl, &self.object.verticies[..], pos_attrib as u32, 3);
-        Cylinder::buffer_u16_indices(gl, &self.object.indicies[..]);
+        Cylinders::buffer_f32_data(gl, &self.object.verticies[..], corner_attrib as u32, 2);
+        Cylinders::buffer_u16_indices(gl, &self.object.indicies[..]);
+
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(self.buffer));
+        Cylinders::instance_attribute(gl, shader, "start", 3, STRIDE, 0);
+        Cylinders::instance_attribute(gl, shader, "end", 3, STRIDE, 3);
+        Cylinders::instance_attribute(gl, shader, "radius", 1, STRIDE, 6);
+        Cylinders::instance_attribute(gl, shader, "color_start", 4, STRIDE, 7);
+        Cylinders::instance_attribute(gl, shader, "color_end", 4, STRIDE, 11);
     }
 
     fn render(&self, gl: &WebGl2RenderingContext, state: &State) {
         #![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
 
-        let shader = self.shader();
-
-        let base_vector = self.position_start.sub(&self.position_end);
-        let height = base_vector.mag();
-        let base_vector_norm = base_vector.scale(1.0 / height);
-
-        let height_uni = shader.get_uniform_location(gl, "height");
-        gl.uniform1f(height_uni.as_ref(), height);
-
-        let radius_uni = shader.get_uniform_location(gl, "radius");
-        gl.uniform1f(radius_uni.as_ref(), self.radius);
-
-        let color_start_uni = shader.get_uniform_location(gl, "color_start");
-        gl.uniform4f(
-            color_start_uni.as_ref(),
-            self.color_end[0],
-            self.color_end[1],
-            self.color_end[2],
-            self.color_end[3],
-        );
-
-        let color_end_uni = shader.get_uniform_location(gl, "color_end");
-        gl.uniform4f(
-            color_end_uni.as_ref(),
-            self.color_start[0],
-            self.color_start[1],
-            self.color_start[2],
-            self.color_start[3],
-        );
-
-        let model_uni = shader.get_uniform_location(gl, "model");
-        let mut model = Mat4::identity();
-
-        let cross_prod = cross(&base_vector_norm, &[0.0, 0.0, 1.0]);
-        let angle = -1.0 * base_vector_norm.dot(&[0.0, 0.0, 1.0]).acos();
+        if self.instances.is_empty() {
+            return;
+        }
 
-        model.translate(&self.position_start.add(&self.position_end).scale(0.5));
-        model.rotate(angle, &cross_prod);
+        Cylinders::update_f32_data(gl, self.buffer, &self.instances);
 
-        gl.uniform_matrix4fv_with_f32_array(model_uni.as_ref(), false, &model);
+        let shader = self.shader();
 
         let view_uni = shader.get_uniform_location(gl, "view");
         let view = state.camera().view();
         gl.uniform_matrix4fv_with_f32_array(view_uni.as_ref(), false, &view);
 
         let perspective_uni = shader.get_uniform_location(gl, "perspective");
-        let perspective = state.camera().projection();
+        let perspective = self
+            .projection
+            .unwrap_or_else(|| state.camera().projection());
         gl.uniform_matrix4fv_with_f32_array(perspective_uni.as_ref(), false, &perspective);
 
         let camera_position_uni = shader.get_uniform_location(gl, "cameraPos");
@@ -167,11 +100,15 @@ impl<'a, 'b> Render<'a> for Cylinder<'a, 'b> {
             camera_postion[2],
         );
 
-        gl.draw_elements_with_i32(
+        let unlit_uni = shader.get_uniform_location(gl, "unlit");
+        gl.uniform1i(unlit_uni.as_ref(), i32::from(self.unlit));
+
+        gl.draw_elements_instanced_with_i32(
             GL::TRIANGLES,
             self.object.indicies.len() as i32,
             GL::UNSIGNED_SHORT,
             0,
+            self.instances.len() as i32 / STRIDE,
         );
     }
 }
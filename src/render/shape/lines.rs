@@ -0,0 +1,107 @@
+use web_sys::WebGl2RenderingContext as GL;
+use web_sys::*;
+
+use crate::app::State;
+use crate::render::shader::Kind;
+use crate::render::shader::Shader;
+use crate::render::shape::Render;
+
+/// Buffers for the vertices of the lines, filled again every frame
+pub struct Buffers {
+    positions: WebGlBuffer,
+    colors: WebGlBuffer,
+}
+
+impl Buffers {
+    pub fn new(gl: &WebGl2RenderingContext) -> Self {
+        Self {
+            positions: gl.create_buffer().unwrap(),
+            colors: gl.create_buffer().unwrap(),
+        }
+    }
+}
+
+/// One pixel wide line segments, each end with its own color
+pub struct Lines<'a, 'b> {
+    pub buffers: &'b Buffers,
+    pub shader: &'a Shader,
+    /// Three coordinates for each end of each segment
+    pub verticies: Vec<f32>,
+    /// Four components for each end of each segment
+    pub colors: Vec<f32>,
+}
+
+impl Lines<'_, '_> {
+    pub fn push(&mut self, start: &[f32; 3], end: &[f32; 3], color: &[f32; 4]) {
+        self.push_colored(start, end, color, color);
+    }
+
+    pub fn push_colored(
+        &mut self,
+        start: &[f32; 3],
+        end: &[f32; 3],
+        color_start: &[f32; 4],
+        color_end: &[f32; 4],
+    ) {
+        self.verticies.extend_from_slice(start);
+        self.verticies.extend_from_slice(end);
+        self.colors.extend_from_slice(color_start);
+        self.colors.extend_from_slice(color_end);
+    }
+}
+
+impl<'a> Render<'a> for Lines<'a, '_> {
+    fn shader_kind() -> Kind {
+        Kind::Basic
+    }
+
+    fn shader(&'a self) -> &'a Shader {
+        self.shader
+    }
+
+    fn buffer_attributes(&self, gl: &WebGl2RenderingContext) {
+        #![allow(clippy::cast_sign_loss)]
+
+        let shader = self.shader();
+
+        let pos_attrib = gl.get_attrib_location(&shader.program, "position");
+        let col_attrib = gl.get_attrib_location(&shader.program, "col");
+
+        gl.enable_vertex_attrib_array(pos_attrib as u32);
+        gl.enable_vertex_attrib_array(col_attrib as u32);
+
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.buffers.positions));
+        gl.vertex_attrib_pointer_with_i32(pos_attrib as u32, 3, GL::FLOAT, false, 0, 0);
+
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.buffers.colors));
+        gl.vertex_attrib_pointer_with_i32(col_attrib as u32, 4, GL::FLOAT, false, 0, 0);
+    }
+
+    fn render(&self, gl: &WebGl2RenderingContext, state: &State) {
+        #![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        use webgl_matrix::Matrix;
+
+        if self.verticies.is_empty() {
+            return;
+        }
+
+        Self::update_f32_data(gl, &self.buffers.positions, &self.verticies);
+        Self::update_f32_data(gl, &self.buffers.colors, &self.colors);
+
+        let shader = self.shader();
+
+        let model_uni = shader.get_uniform_location(gl, "model");
+        let model = webgl_matrix::Mat4::identity();
+        gl.uniform_matrix4fv_with_f32_array(model_uni.as_ref(), false, &model);
+
+        let view_uni = shader.get_uniform_location(gl, "view");
+        let view = &state.camera().view();
+        gl.uniform_matrix4fv_with_f32_array(view_uni.as_ref(), false, view);
+
+        let perspective_uni = shader.get_uniform_location(gl, "perspective");
+        let perspective = &state.camera().projection();
+        gl.uniform_matrix4fv_with_f32_array(perspective_uni.as_ref(), false, perspective);
+
+        gl.draw_arrays(GL::LINES, 0, (self.verticies.len() / 3) as i32);
+    }
+}
@@ -2,15 +2,21 @@ use js_sys::WebAssembly;
 use wasm_bindgen::JsCast;
 use web_sys::WebGl2RenderingContext as GL;
 
+use web_sys::WebGlBuffer;
+
 use crate::app::State;
 use crate::render::shader::Kind;
 use crate::render::shader::Shader;
 
 pub mod cylinder;
+pub mod lines;
+pub mod quad;
 pub mod sphere;
+pub mod text;
 pub mod triangle;
 
 pub trait Render<'a> {
+    #[allow(dead_code)]
     fn shader_kind() -> Kind;
 
     fn shader(&'a self) -> &'a Shader;
@@ -39,6 +45,7 @@ pub trait Render<'a> {
         gl.vertex_attrib_pointer_with_i32(attrib, size, GL::FLOAT, false, 0, 0);
     }
 
+    #[allow(dead_code)]
     fn buffer_u8_data(gl: &GL, data: &[u8], attrib: u32, size: i32) {
         #![allow(clippy::cast_possible_truncation)]
 
@@ -59,6 +66,42 @@ pub trait Render<'a> {
         gl.vertex_attrib_pointer_with_i32(attrib, size, GL::UNSIGNED_BYTE, false, 0, 0);
     }
 
+    /// Replace the contents of a buffer which is filled again every frame
+    fn update_f32_data(gl: &GL, buffer: &WebGlBuffer, data: &[f32]) {
+        #![allow(clippy::cast_possible_truncation)]
+
+        let memory_buffer = wasm_bindgen::memory()
+            .dyn_into::<WebAssembly::Memory>()
+            .unwrap()
+            .buffer();
+
+        let data_location = data.as_ptr() as u32 / 4;
+
+        let data_array = js_sys::Float32Array::new(&memory_buffer)
+            .subarray(data_location, data_location + data.len() as u32);
+
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(buffer));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &data_array, GL::DYNAMIC_DRAW);
+    }
+
+    /// Read an attribute from the buffer bound to `ARRAY_BUFFER` once per instance, as
+    /// `size` floats found `offset` floats into each record of `stride` floats
+    fn instance_attribute(
+        gl: &GL,
+        shader: &Shader,
+        name: &str,
+        size: i32,
+        stride: i32,
+        offset: i32,
+    ) {
+        #![allow(clippy::cast_sign_loss)]
+
+        let attrib = gl.get_attrib_location(&shader.program, name) as u32;
+        gl.enable_vertex_attrib_array(attrib);
+        gl.vertex_attrib_pointer_with_i32(attrib, size, GL::FLOAT, false, stride * 4, offset * 4);
+        gl.vertex_attrib_divisor(attrib, 1);
+    }
+
     fn buffer_u16_indices(gl: &GL, indices: &[u16]) {
         #![allow(clippy::cast_possible_truncation)]
 
@@ -0,0 +1,16 @@
+/// A square from (-1, -1) to (1, 1), as two triangles. The sphere and cylinder
+/// shaders place its corners for each instance and ray-cast the shape inside it.
+#[allow(clippy::upper_case_acronyms)]
+pub struct VBO {
+    pub verticies: Vec<f32>,
+    pub indicies: Vec<u16>,
+}
+
+impl VBO {
+    pub fn new() -> Self {
+        Self {
+            verticies: vec![-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0],
+            indicies: vec![0, 1, 2, 2, 1, 3],
+        }
+    }
+}
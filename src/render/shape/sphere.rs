@@ -1,89 +1,40 @@
 use web_sys::WebGl2RenderingContext as GL;
 use web_sys::*;
-use webgl_matrix::{Mat4, Matrix};
 
 use crate::app::State;
 use crate::render::shader::Kind;
 use crate::render::shader::Shader;
+use crate::render::shape::quad::VBO;
 use crate::render::shape::Render;
 
-pub struct VBO {
-    pub verticies: Vec<f32>,
-    pub indicies: Vec<u16>,
-}
+/// Floats for each sphere: the center, the radius and the color
+const STRIDE: i32 = 8;
 
-pub struct Sphere<'a, 'b> {
+/// Every sphere of a pass, each ray-cast within an instance of the quad, in one call
+pub struct Spheres<'a, 'b> {
     pub object: &'b VBO,
+    /// Filled with the `instances` on every render
+    pub buffer: &'b WebGlBuffer,
     pub shader: &'a Shader,
-    pub radius: f32,
-    pub color: [f32; 4],
-    pub position: [f32; 3],
+    pub instances: Vec<f32>,
+    /// Draw the colors as they are, without lighting, such as when picking
+    pub unlit: bool,
+    /// Replaces the projection of the camera, such as by one narrowed to the pixel
+    /// being picked
+    pub projection: Option<[f32; 16]>,
 }
 
-impl VBO {
-    pub fn new(lat_bands: u16, long_bands: u16) -> Self {
-        use std::f32::consts::PI;
-
-        let mut vertex_position_data = Vec::<f32>::new();
-        let mut index_data = Vec::<u16>::new();
-
-        for lat_number in 0..lat_bands {
-            let theta = f32::from(lat_number) * PI / f32::from(lat_bands);
-            let sin_theta = theta.sin();
-            let cos_theta = theta.cos();
-
-            for long_number in 0..long_bands {
-                let phi = f32::from(long_number) * 2.0 * PI / f32::from(long_bands);
-                let sin_phi = phi.sin();
-                let cos_phi = phi.cos();
-
-                let x = cos_phi * sin_theta;
-                let y = cos_theta;
-                let z = sin_phi * sin_theta;
-
-                vertex_position_data.push(x);
-                vertex_position_data.push(y);
-                vertex_position_data.push(z);
-
-                let first = lat_number * long_bands + long_number;
-                let second = first + long_bands;
-
-                if second >= lat_bands * long_bands - 1 {
-                    index_data.push(first);
-                    index_data.push(lat_bands * long_bands);
-                    index_data.push(first + 1);
-
-                    index_data.push(lat_bands * long_bands);
-                    index_data.push(first);
-                    index_data.push(first + 1);
-
-                    continue;
-                }
-
-                index_data.push(first);
-                index_data.push(second);
-                index_data.push(first + 1);
-
-                index_data.push(second);
-                index_data.push(second + 1);
-                index_data.push(first + 1);
-            }
-        }
-
-        vertex_position_data.push(0.0);
-        vertex_position_data.push(-1.0);
-        vertex_position_data.push(0.0);
-
-        Self {
-            verticies: vertex_position_data,
-            indicies: index_data,
-        }
+impl Spheres<'_, '_> {
+    pub fn push(&mut self, position: &[f32; 3], radius: f32, color: &[f32; 4]) {
+        self.instances.extend_from_slice(position);
+        self.instances.push(radius);
+        self.instances.extend_from_slice(color);
     }
 }
 
-impl<'a, 'b> Render<'a> for Sphere<'a, 'b> {
+impl<'a> Render<'a> for Spheres<'a, '_> {
     fn shader_kind() -> Kind {
-        Kind::Basic
+        Kind::Sphere
     }
 
     fn shader(&'a self) -> &'a Shader {
@@ -95,41 +46,37 @@ impl<'a, 'b> Render<'a> for Sphere<'a, 'b> {
 
         let shader = self.shader();
 
-        let pos_attrib = gl.get_attrib_location(&shader.program, "position");
-        gl.enable_vertex_attrib_array(pos_attrib as u32);
+        let corner_attrib = gl.get_attrib_location(&shader.program, "corner");
+        gl.enable_vertex_attrib_array(corner_attrib as u32);
+
+        Spheres::buffer_f32_data(gl, &self.object.verticies[..], corner_attrib as u32, 2);
+        Spheres::buffer_u16_indices(gl, &self.object.indicies[..]);
 
-        Sphere::buffer_f32_data(gl, &self.object.verticies[..], pos_attrib as u32, 3);
-        Sphere::buffer_u16_indices(gl, &self.object.indicies[..]);
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(self.buffer));
+        Spheres::instance_attribute(gl, shader, "center", 3, STRIDE, 0);
+        Spheres::instance_attribute(gl, shader, "radius", 1, STRIDE, 3);
+        Spheres::instance_attribute(gl, shader, "color", 4, STRIDE, 4);
     }
 
     fn render(&self, gl: &WebGl2RenderingContext, state: &State) {
         #![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
 
-        let shader = self.shader();
-
-        let radius_uni = shader.get_uniform_location(gl, "radius");
-        gl.uniform1f(radius_uni.as_ref(), self.radius);
+        if self.instances.is_empty() {
+            return;
+        }
 
-        let color_uni = shader.get_uniform_location(gl, "color");
-        gl.uniform4f(
-            color_uni.as_ref(),
-            self.color[0],
-            self.color[1],
-            self.color[2],
-            self.color[3],
-        );
+        Spheres::update_f32_data(gl, self.buffer, &self.instances);
 
-        let model_uni = shader.get_uniform_location(gl, "model");
-        let mut model = Mat4::identity();
-        model.translate(&self.position);
-        gl.uniform_matrix4fv_with_f32_array(model_uni.as_ref(), false, &model);
+        let shader = self.shader();
 
         let view_uni = shader.get_uniform_location(gl, "view");
         let view = state.camera().view();
         gl.uniform_matrix4fv_with_f32_array(view_uni.as_ref(), false, &view);
 
         let perspective_uni = shader.get_uniform_location(gl, "perspective");
-        let perspective = state.camera().projection();
+        let perspective = self
+            .projection
+            .unwrap_or_else(|| state.camera().projection());
         gl.uniform_matrix4fv_with_f32_array(perspective_uni.as_ref(), false, &perspective);
 
         let camera_position_uni = shader.get_uniform_location(gl, "cameraPos");
@@ -141,11 +88,15 @@ impl<'a, 'b> Render<'a> for Sphere<'a, 'b> {
             camera_postion[2],
         );
 
-        gl.draw_elements_with_i32(
+        let unlit_uni = shader.get_uniform_location(gl, "unlit");
+        gl.uniform1i(unlit_uni.as_ref(), i32::from(self.unlit));
+
+        gl.draw_elements_instanced_with_i32(
             GL::TRIANGLES,
             self.object.indicies.len() as i32,
             GL::UNSIGNED_SHORT,
             0,
+            self.instances.len() as i32 / STRIDE,
         );
     }
 }
@@ -0,0 +1,182 @@
+use wasm_bindgen::prelude::*;
+use web_sys::WebGl2RenderingContext as GL;
+use web_sys::*;
+
+use crate::app::State;
+use crate::render::shader::Kind;
+use crate::render::shader::Shader;
+use crate::render::shape::quad::VBO;
+use crate::render::shape::Render;
+
+/// The glyph atlas and its layout, baked by `build.rs` from `font.txt`
+mod font {
+    include!(concat!(env!("OUT_DIR"), "/font.rs"));
+}
+
+/// Distance between the starts of consecutive glyphs, in units of the font
+const ADVANCE: f32 = 5.0;
+
+/// Width of a glyph without the space after it
+const GLYPH_WIDTH: f32 = 4.0;
+
+/// Height of capitals and digits above the baseline, which sits this high in each glyph
+const CAP_HEIGHT: f32 = 6.0;
+const BASELINE: f32 = 2.0;
+
+/// Floats for each glyph: the anchor, the offset, the cell and the color
+const STRIDE: i32 = 11;
+
+/// Upload the glyph atlas as a single channel texture
+pub fn create_atlas(gl: &WebGl2RenderingContext) -> Result<WebGlTexture, JsValue> {
+    #![allow(clippy::cast_possible_wrap)]
+
+    let texture = gl
+        .create_texture()
+        .ok_or_else(|| JsValue::from_str("Could not create the glyph atlas"))?;
+
+    gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+    gl.pixel_storei(GL::UNPACK_ALIGNMENT, 1);
+    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+        GL::TEXTURE_2D,
+        0,
+        GL::R8 as i32,
+        font::ATLAS_WIDTH,
+        font::ATLAS_HEIGHT,
+        0,
+        GL::RED,
+        GL::UNSIGNED_BYTE,
+        Some(font::ATLAS),
+    )?;
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+
+    Ok(texture)
+}
+
+/// Every label of a pass, with each glyph drawn as an instance of the quad facing the
+/// camera, in one call
+pub struct Text<'a, 'b> {
+    pub object: &'b VBO,
+    /// Filled with the `instances` on every render
+    pub buffer: &'b WebGlBuffer,
+    pub atlas: &'b WebGlTexture,
+    pub shader: &'a Shader,
+    pub instances: Vec<f32>,
+    /// Height of capitals and digits, in Angstroms
+    pub height: f32,
+}
+
+impl Text<'_, '_> {
+    /// A line of text centred on `anchor`. Characters missing from the font are drawn
+    /// as question marks.
+    pub fn push(&mut self, anchor: &[f32; 3], text: &str, color: &[f32; 4]) {
+        #![allow(clippy::cast_precision_loss)]
+
+        let unit = self.height / CAP_HEIGHT;
+        let count = text.chars().count() as f32;
+        let left = -(ADVANCE * (count - 1.0) + GLYPH_WIDTH) / 2.0;
+        let bottom = -(BASELINE + CAP_HEIGHT / 2.0) - font::CELL_MARGIN;
+
+        for (i, character) in text.chars().enumerate() {
+            if character == ' ' {
+                continue;
+            }
+
+            let index = font::CHARACTERS
+                .chars()
+                .position(|glyph| glyph == character)
+                .or_else(|| font::CHARACTERS.chars().position(|glyph| glyph == '?'))
+                .unwrap_or(0);
+
+            let x = left + ADVANCE * i as f32 - font::CELL_MARGIN;
+
+            self.instances.extend_from_slice(anchor);
+            self.instances.push(x * unit);
+            self.instances.push(bottom * unit);
+            self.instances
+                .push((index % font::ATLAS_COLUMNS) as f32 / font::ATLAS_COLUMNS as f32);
+            self.instances
+                .push((index / font::ATLAS_COLUMNS) as f32 / font::ATLAS_ROWS as f32);
+            self.instances.extend_from_slice(color);
+        }
+    }
+}
+
+impl<'a> Render<'a> for Text<'a, '_> {
+    fn shader_kind() -> Kind {
+        Kind::Text
+    }
+
+    fn shader(&'a self) -> &'a Shader {
+        self.shader
+    }
+
+    fn buffer_attributes(&self, gl: &WebGl2RenderingContext) {
+        #![allow(clippy::cast_sign_loss)]
+
+        let shader = self.shader();
+
+        let corner_attrib = gl.get_attrib_location(&shader.program, "corner");
+        gl.enable_vertex_attrib_array(corner_attrib as u32);
+
+        Text::buffer_f32_data(gl, &self.object.verticies[..], corner_attrib as u32, 2);
+        Text::buffer_u16_indices(gl, &self.object.indicies[..]);
+
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(self.buffer));
+        Text::instance_attribute(gl, shader, "anchor", 3, STRIDE, 0);
+        Text::instance_attribute(gl, shader, "offset", 2, STRIDE, 3);
+        Text::instance_attribute(gl, shader, "cell", 2, STRIDE, 5);
+        Text::instance_attribute(gl, shader, "color", 4, STRIDE, 7);
+    }
+
+    fn render(&self, gl: &WebGl2RenderingContext, state: &State) {
+        #![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        #![allow(clippy::cast_precision_loss)]
+
+        if self.instances.is_empty() {
+            return;
+        }
+
+        Text::update_f32_data(gl, self.buffer, &self.instances);
+
+        let shader = self.shader();
+
+        let view_uni = shader.get_uniform_location(gl, "view");
+        let view = state.camera().view();
+        gl.uniform_matrix4fv_with_f32_array(view_uni.as_ref(), false, &view);
+
+        let perspective_uni = shader.get_uniform_location(gl, "perspective");
+        let perspective = state.camera().projection();
+        gl.uniform_matrix4fv_with_f32_array(perspective_uni.as_ref(), false, &perspective);
+
+        let unit = self.height / CAP_HEIGHT;
+        let glyph_size_uni = shader.get_uniform_location(gl, "glyphSize");
+        gl.uniform2f(
+            glyph_size_uni.as_ref(),
+            font::CELL_WIDTH * unit,
+            font::CELL_HEIGHT * unit,
+        );
+
+        let cell_size_uni = shader.get_uniform_location(gl, "cellSize");
+        gl.uniform2f(
+            cell_size_uni.as_ref(),
+            1.0 / font::ATLAS_COLUMNS as f32,
+            1.0 / font::ATLAS_ROWS as f32,
+        );
+
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, Some(self.atlas));
+        let atlas_uni = shader.get_uniform_location(gl, "atlas");
+        gl.uniform1i(atlas_uni.as_ref(), 0);
+
+        gl.draw_elements_instanced_with_i32(
+            GL::TRIANGLES,
+            self.object.indicies.len() as i32,
+            GL::UNSIGNED_SHORT,
+            0,
+            self.instances.len() as i32 / STRIDE,
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+use web_sys::WebGl2RenderingContext as GL;
+use web_sys::*;
+
+use super::framebuffer::Framebuffer;
+use super::{png, WebRenderer, BACKGROUND_COLOR};
+use crate::app::State;
+
+/// Most samples kept of each pixel of a snapshot, which smooth its edges as the
+/// canvas does
+const MAX_SAMPLES: i32 = 4;
+
+impl WebRenderer {
+    /// Draw the scene offscreen at any size the GPU allows, however large the canvas
+    /// is, and encode it as a PNG image. The camera should already fit the size.
+    pub fn snapshot(
+        &self,
+        gl: &WebGl2RenderingContext,
+        state: &State,
+        width: u32,
+        height: u32,
+        transparent_background: bool,
+    ) -> Result<Vec<u8>, JsValue> {
+        #![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        #![allow(clippy::cast_sign_loss)]
+
+        let largest = gl
+            .get_parameter(GL::MAX_RENDERBUFFER_SIZE)?
+            .as_f64()
+            .map_or(0, |size| size as u32);
+
+        if width == 0 || height == 0 || width > largest || height > largest {
+            return Err(JsValue::from_str(&format!(
+                "Snapshots can be from 1x1 to {}x{} pixels, not {}x{}",
+                largest, largest, width, height
+            )));
+        }
+
+        let background = if transparent_background {
+            [0.0; 4]
+        } else {
+            BACKGROUND_COLOR
+        };
+
+        let samples = gl
+            .get_parameter(GL::MAX_SAMPLES)?
+            .as_f64()
+            .map_or(0, |samples| samples as i32)
+            .min(MAX_SAMPLES);
+
+        let (width_px, height_px) = (width as i32, height as i32);
+        let multisampled = Framebuffer::multisampled(gl, width_px, height_px, samples)?;
+        let resolved = Framebuffer::new(gl, width_px, height_px);
+
+        let pixels = resolved.and_then(|resolved| {
+            multisampled.bind(gl);
+            let pixels = self.draw(gl, state, &background).and_then(|()| {
+                multisampled.resolve(gl, &resolved);
+                resolved.bind(gl);
+                resolved.read(gl, 0, 0, width_px, height_px)
+            });
+            resolved.delete(gl);
+            pixels
+        });
+        Framebuffer::unbind(gl);
+        multisampled.delete(gl);
+
+        let mut pixels = pixels?;
+
+        // Translucent pixels were blended with premultiplied colors, while PNG keeps
+        // them apart from the alpha
+        for pixel in pixels.chunks_exact_mut(4) {
+            let alpha = u32::from(pixel[3]);
+            if alpha > 0 && alpha < 255 {
+                for channel in &mut pixel[..3] {
+                    *channel = ((u32::from(*channel) * 255 + alpha / 2) / alpha).min(255) as u8;
+                }
+            }
+        }
+
+        // The rows were read from the bottom up
+        let rows: Vec<u8> = pixels
+            .chunks_exact(width as usize * 4)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+
+        Ok(png::encode(width, height, &rows))
+    }
+}